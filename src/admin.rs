@@ -0,0 +1,40 @@
+use std::env;
+use actix_web::{http::header::AUTHORIZATION, HttpRequest};
+
+use crate::error::{error, ErrorCode};
+
+/// The bearer tokens `/admin/...` routes accept, each with the name the audit
+/// log records: `ADMIN_TOKEN`, named `admin`, and `ADMIN_TOKENS`, a comma
+/// separated list of `name:token`. Without any those routes are disabled.
+pub struct AdminToken(Vec<(String, String)>);
+
+impl AdminToken {
+    pub fn from_env() -> AdminToken {
+        let single = env::var("ADMIN_TOKEN").ok().map(|t| ("admin".to_string(), t));
+        let named = env::var("ADMIN_TOKENS").unwrap_or_default().split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| match entry.trim().split_once(':') {
+                Some((name, token)) => (name.to_string(), token.to_string()),
+                None => panic!("Invalid ADMIN_TOKENS entry {:?}, expected name:token", entry),
+            })
+            .collect::<Vec<_>>();
+        AdminToken(single.into_iter().chain(named).filter(|(_, t)| !t.is_empty()).collect())
+    }
+
+    /// The name of the token `req` carries as `Authorization: Bearer <token>`,
+    /// if it is one of them.
+    pub fn name(&self, req: &HttpRequest) -> Option<&str> {
+        let given = req.headers().get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))?;
+        self.0.iter().find(|(_, token)| token == given).map(|(name, _)| name.as_str())
+    }
+
+    /// Fails unless `req` carries one of the tokens, and returns its name.
+    pub fn authorize(&self, req: &HttpRequest) -> actix_web::Result<String> {
+        if self.0.is_empty() {
+            return Err(error(ErrorCode::Unauthorized, "Admin routes are disabled, set ADMIN_TOKEN or ADMIN_TOKENS to enable them"));
+        }
+        self.name(req).map(str::to_string).ok_or_else(|| error(ErrorCode::Unauthorized, "Missing or wrong admin token"))
+    }
+}
@@ -0,0 +1,188 @@
+use std::str::FromStr;
+use itertools::Itertools;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// How letters are folded before comparing them, read from `CASE_FOLDING`.
+#[derive(Debug, Default, Copy, Clone)]
+pub enum CaseFolding {
+    #[default]
+    Upper,
+    Lower,
+    /// Uppercase with Turkish and Azerbaijani rules: `i` becomes `İ` and `ı`
+    /// becomes `I`.
+    TurkicUpper,
+    /// Lowercase with Turkish and Azerbaijani rules: `I` becomes `ı` and `İ`
+    /// becomes `i`.
+    TurkicLower,
+    /// Letters are compared as written.
+    None,
+}
+
+impl FromStr for CaseFolding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "upper" => Ok(CaseFolding::Upper),
+            "lower" => Ok(CaseFolding::Lower),
+            "turkic_upper" => Ok(CaseFolding::TurkicUpper),
+            "turkic_lower" => Ok(CaseFolding::TurkicLower),
+            "none" => Ok(CaseFolding::None),
+            _ => Err(()),
+        }
+    }
+}
+
+impl CaseFolding {
+    fn fold(&self, text: &str) -> String {
+        match self {
+            CaseFolding::Upper => text.to_uppercase(),
+            CaseFolding::Lower => text.to_lowercase(),
+            CaseFolding::TurkicUpper => text.chars()
+                .map(|c| match c {
+                    'i' => 'İ',
+                    'ı' => 'I',
+                    c => c,
+                })
+                .collect::<String>()
+                .to_uppercase(),
+            CaseFolding::TurkicLower => text.chars()
+                .map(|c| match c {
+                    'I' => 'ı',
+                    'İ' => 'i',
+                    c => c,
+                })
+                .collect::<String>()
+                .to_lowercase(),
+            CaseFolding::None => text.to_string(),
+        }
+    }
+}
+
+/// The tiles words are spelled with, read from `ALPHABET_FILE`: one tile per
+/// line, either a single letter (`Ñ`) or a multi-character spelling followed by
+/// the single character that stands for it everywhere (`L·L Ŀ`). Blank lines
+/// and `#` comments are skipped. Without the file, the alphabet is made of the
+/// letters the corpus uses.
+///
+/// Words and clues are compared in Unicode NFC form, so that composed and
+/// decomposed spellings of the same letter match, and folded to `case`.
+/// Diacritics can be folded too (`é` to `e`), except on letters the alphabet
+/// file lists, so that accents don't count.
+#[derive(Debug, Default, Clone)]
+pub struct Alphabet {
+    case: CaseFolding,
+    fold_diacritics: bool,
+    /// Single-character tiles; any letter is accepted when empty, which only
+    /// happens with an empty corpus.
+    letters: Vec<char>,
+    /// Folded multi-character spellings and their tile, longest first.
+    digraphs: Vec<(Vec<char>, char)>,
+}
+
+impl Alphabet {
+    pub fn new(case: CaseFolding) -> Alphabet {
+        Alphabet { case, ..Alphabet::default() }
+    }
+
+    pub fn parse(text: &str, case: CaseFolding) -> Result<Alphabet, String> {
+        let mut alphabet = Alphabet::new(case);
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let tile = |s: &str| {
+                let mut chars = alphabet.normalize(s).chars().collect::<Vec<_>>();
+                match chars.len() {
+                    1 => chars.pop(),
+                    _ => None,
+                }
+            };
+            match fields.as_slice() {
+                [letter] => alphabet.letters.push(tile(letter).ok_or_else(|| format!("Tile {:?} needs a single character to stand for it", letter))?),
+                [spelling, letter] => {
+                    let letter = tile(letter).ok_or_else(|| format!("Invalid alphabet line: {:?}", line))?;
+                    alphabet.letters.push(letter);
+                    alphabet.digraphs.push((alphabet.normalize(spelling).chars().collect(), letter));
+                },
+                _ => return Err(format!("Invalid alphabet line: {:?}", line)),
+            }
+        }
+        alphabet.digraphs.sort_by_key(|(spelling, _)| std::cmp::Reverse(spelling.len()));
+        Ok(alphabet)
+    }
+
+    pub fn folding_diacritics(self, fold_diacritics: bool) -> Alphabet {
+        Alphabet { fold_diacritics, ..self }
+    }
+
+    /// Without an alphabet file, takes the letters that `words` use as the
+    /// alphabet, so that clues with letters foreign to the corpus are refused.
+    pub fn derive_letters<'a>(&mut self, words: impl Iterator<Item = &'a str>) {
+        if self.letters.is_empty() {
+            self.letters = words.flat_map(str::chars).filter(|c| c.is_alphabetic()).unique().collect();
+        }
+    }
+
+    /// `text` in NFC form and folded, without replacing multi-character
+    /// spellings.
+    pub fn normalize(&self, text: &str) -> String {
+        let text = self.case.fold(&text.nfc().collect::<String>());
+        match self.fold_diacritics {
+            false => text,
+            true => text.chars()
+                .flat_map(|c| match self.letters.contains(&c) {
+                    true => vec![c],
+                    false => c.encode_utf8(&mut [0; 4]).nfd().filter(|m| !is_combining_mark(*m)).nfc().collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// How `c` is compared: its first character once folded.
+    pub fn fold_letter(&self, c: char) -> char {
+        self.normalize(c.encode_utf8(&mut [0; 4])).chars().next().unwrap_or(c)
+    }
+
+    /// Whether `c` is `A`, `E`, `I`, `O` or `U`, accents aside; `Y` and the
+    /// vowels of other scripts count as consonants.
+    pub fn is_vowel(&self, c: char) -> bool {
+        c.encode_utf8(&mut [0; 4]).nfd().next()
+            .is_some_and(|base| matches!(base.to_ascii_lowercase(), 'a' | 'e' | 'i' | 'o' | 'u'))
+    }
+
+    pub fn is_letter(&self, c: char) -> bool {
+        match self.letters.is_empty() {
+            true => c.is_alphabetic(),
+            false => self.letters.contains(&self.fold_letter(c)),
+        }
+    }
+
+    /// The tile spelled by the start of `text` with several characters, and
+    /// how many characters it spans.
+    pub fn digraph(&self, text: &[char]) -> Option<(char, usize)> {
+        self.digraphs.iter()
+            .find(|(spelling, _)| text.len() >= spelling.len()
+                && text.iter().zip(spelling).all(|(c, s)| self.fold_letter(*c) == *s))
+            .map(|(spelling, tile)| (*tile, spelling.len()))
+    }
+
+    /// `text` normalized, with every multi-character spelling replaced by its
+    /// tile, so that it can be compared with the corpus.
+    pub fn word(&self, text: &str) -> String {
+        let chars = self.normalize(text).chars().collect::<Vec<_>>();
+        let mut word = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match self.digraph(&chars[i..]) {
+                Some((tile, len)) => {
+                    word.push(tile);
+                    i += len;
+                },
+                None => {
+                    word.push(chars[i]);
+                    i += 1;
+                },
+            }
+        }
+        word
+    }
+}
@@ -0,0 +1,77 @@
+use std::{fs::{self, File, OpenOptions}, io::{self, Write}, sync::{Mutex, PoisonError}, time::{SystemTime, UNIX_EPOCH}};
+use actix_web::HttpRequest;
+use serde::{Deserialize, Serialize};
+
+use crate::encoding::Protobuf;
+
+/// A change made to the corpora while the server runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    /// The name of the admin token used, `anonymous` without one.
+    pub who: String,
+    /// The address the request came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// What was changed, named after the route that changed it.
+    pub action: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+}
+
+impl Protobuf for Vec<AuditEntry> {}
+
+/// Every change made so far, oldest first. With `AUDIT_LOG_FILE` set they are
+/// also appended to that file, one JSON object per line, and the file is read
+/// back on start so the log survives restarts.
+#[derive(Default)]
+pub struct AuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+    file: Option<Mutex<File>>,
+}
+
+impl AuditLog {
+    pub fn open(path: Option<&str>) -> Result<AuditLog, String> {
+        let Some(path) = path else { return Ok(AuditLog::default()) };
+        let entries = match fs::read_to_string(path) {
+            Ok(text) => text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("Invalid audit log entry at {}:{}: {}", path, i + 1, e)))
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read audit log {}: {}", path, e)),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)
+            .map_err(|e| format!("Failed to open audit log {}: {}", path, e))?;
+        Ok(AuditLog { entries: Mutex::new(entries), file: Some(Mutex::new(file)) })
+    }
+
+    /// Records that `who` did `action` through `req`. A failure to write the
+    /// file is reported but doesn't undo the change, which is already made.
+    pub fn record(&self, req: &HttpRequest, who: &str, action: &str, detail: String) {
+        let entry = AuditEntry {
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            who: who.to_string(),
+            address: req.peer_addr().map(|a| a.ip().to_string()),
+            action: action.to_string(),
+            detail,
+        };
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(file) = &self.file {
+            let line = serde_json::to_string(&entry).expect("Audit entries serialize") + "\n";
+            let written = file.lock().unwrap_or_else(PoisonError::into_inner).write_all(line.as_bytes());
+            if let Err(e) = written {
+                eprintln!("audit: failed to write {} by {}: {}", action, who, e);
+            }
+        }
+        entries.push(entry);
+    }
+
+    /// The last `limit` entries, oldest first.
+    pub fn entries(&self, limit: usize) -> Vec<AuditEntry> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries[entries.len().saturating_sub(limit)..].to_vec()
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::encoding::Protobuf;
+
+const MAX_REPRESENTATIVES: usize = 3;
+
+#[derive(Debug, Serialize)]
+pub struct Cluster {
+    pattern: String,
+    size: usize,
+    words: Vec<String>,
+}
+
+impl Protobuf for Vec<Cluster> {}
+
+fn mask(word: &str, pos: usize) -> String {
+    word.chars().enumerate().map(|(i, c)| if i == pos { '_' } else { c }).collect()
+}
+
+/// Groups words that share every letter but one (e.g. `_IGHT`), largest groups
+/// first. Each word is assigned to the largest group it belongs to; words that
+/// share no such pattern with any other word end up in a cluster of their own.
+pub fn cluster(words: &[&str]) -> Vec<Cluster> {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, word) in words.iter().enumerate() {
+        for pos in 0..word.chars().count() {
+            groups.entry(mask(word, pos)).or_default().push(idx);
+        }
+    }
+
+    let mut assigned = vec![false; words.len()];
+    let mut clusters = Vec::new();
+    for (pattern, members) in groups.into_iter()
+        .filter(|(_, m)| m.len() > 1)
+        .sorted_by(|(pa, ma), (pb, mb)| mb.len().cmp(&ma.len()).then(pa.cmp(pb)))
+    {
+        let members = members.into_iter().filter(|&i| !assigned[i]).collect::<Vec<_>>();
+        if members.len() < 2 {
+            continue;
+        }
+        for &i in members.iter() {
+            assigned[i] = true;
+        }
+        clusters.push(Cluster {
+            pattern,
+            size: members.len(),
+            words: members.into_iter().take(MAX_REPRESENTATIVES).map(|i| words[i].to_owned()).collect(),
+        });
+    }
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.size));
+    clusters.extend(words.iter().enumerate().filter(|(i, _)| !assigned[*i]).map(|(_, w)| Cluster {
+        pattern: w.to_string(),
+        size: 1,
+        words: vec![w.to_string()],
+    }));
+    clusters
+}
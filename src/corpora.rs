@@ -0,0 +1,442 @@
+use std::{collections::HashMap, env, fs, io::{self, Read}, path::Path, rc::Rc, sync::{Arc, Mutex, PoisonError}, time::{Duration, Instant}};
+use actix_web::{dev::{Extensions, ServiceRequest}, http::Uri, web};
+use arc_swap::ArcSwap;
+use flate2::read::GzDecoder;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{alphabet::Alphabet, error::bad_request, remote, AppState, Settings};
+
+/// Extensions of the compressed word lists `read_file` understands.
+const COMPRESSED: [&str; 2] = [".gz", ".zst"];
+
+/// Where the corpus is read from when `CORPUS_FILE` and `CORPUS_URL` aren't
+/// set.
+const DEFAULT_CORPUS: &str = "data/corpus.txt";
+/// Where past answers are read from when `PAST_ANSWERS_FILE` isn't set, if it
+/// exists.
+const DEFAULT_PAST_ANSWERS: &str = "data/past_answers.txt";
+/// Stands for the English five-letter list built into the binary, used when
+/// there is no corpus file at all.
+pub const EMBEDDED: &str = "<embedded>";
+const EMBEDDED_WORDS: &str = include_str!("../data/words.txt");
+
+/// A word list file, decompressed when its name ends in `.gz` (gzip) or `.zst`
+/// (zstd).
+pub fn read_file(path: &str) -> io::Result<String> {
+    if path == EMBEDDED {
+        return Ok(EMBEDDED_WORDS.to_string());
+    }
+    let file = fs::File::open(path)?;
+    let mut reader: Box<dyn Read> = match path {
+        p if p.ends_with(".gz") => Box::new(GzDecoder::new(file)),
+        p if p.ends_with(".zst") => Box::new(zstd::Decoder::new(file)?),
+        _ => Box::new(file),
+    };
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Fails unless `path` is a plain-text word list that can be rewritten: not
+/// compressed, downloaded or embedded.
+fn writable(path: &str, url: Option<&String>) -> io::Result<()> {
+    match path == EMBEDDED || url.is_some() || COMPRESSED.iter().any(|extension| path.ends_with(extension)) {
+        true => Err(io::Error::other(format!("{} can't be written to", path))),
+        false => Ok(()),
+    }
+}
+
+/// Replaces the contents of `path` at once, so that a failed write never
+/// leaves half a word list behind.
+fn replace(path: &str, text: String) -> io::Result<()> {
+    let partial = format!("{}.part", path);
+    fs::write(&partial, text)?;
+    fs::rename(&partial, path)
+}
+
+/// Writes the addition of `spelling` (`word` once normalized) to the corpus
+/// file of `files`, or its removal from both the corpus and the answers
+/// files.
+pub fn persist_word(files: &CorpusFiles, alphabet: &Alphabet, spelling: &str, word: &str, add: bool) -> io::Result<()> {
+    writable(&files.corpus, files.url.as_ref())?;
+    if add {
+        let mut text = fs::read_to_string(&files.corpus)?;
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(spelling);
+        text.push('\n');
+        return replace(&files.corpus, text);
+    }
+    for path in [Some(&files.corpus), files.answers.as_ref()].into_iter().flatten() {
+        writable(path, None)?;
+        let text = fs::read_to_string(path)?;
+        let kept = text.lines().filter(|line| alphabet.word(line.trim()) != word).map(|line| format!("{}\n", line)).collect::<String>();
+        replace(path, kept)?;
+    }
+    Ok(())
+}
+
+/// Where a corpus and its companion files are read from.
+#[derive(Debug, Clone)]
+pub struct CorpusFiles {
+    pub corpus: String,
+    /// Where `corpus` is downloaded from, to be refreshed on every load.
+    pub url: Option<String>,
+    pub answers: Option<String>,
+    pub alphabet: Option<String>,
+    pub frequencies: Option<String>,
+    pub opening_book: Option<String>,
+    pub past_answers: Option<String>,
+    pub proper_nouns: Option<String>,
+    pub denylist: Option<String>,
+    /// Whether accents are ignored, so that the corpus can be played with a
+    /// plain keyboard.
+    pub fold_diacritics: bool,
+}
+
+impl CorpusFiles {
+    /// The default corpus: `CORPUS_FILE`, `ANSWERS_FILE`, `ALPHABET_FILE`,
+    /// `FREQUENCY_FILE`, `OPENING_BOOK`, `PAST_ANSWERS_FILE` (else
+    /// `data/past_answers.txt`), `PROPER_NOUNS_FILE` and `DENYLIST_FILE`,
+    /// folding diacritics if `FOLD_DIACRITICS` is `true`. With `CORPUS_URL`, the corpus is
+    /// downloaded from there and `CORPUS_FILE` is where it is cached. Without
+    /// either, `data/corpus.txt` is used if it exists, and the embedded list
+    /// otherwise.
+    pub fn from_env() -> CorpusFiles {
+        let url = env::var("CORPUS_URL").ok();
+        CorpusFiles {
+            corpus: env::var("CORPUS_FILE").ok()
+                .or(url.as_ref().map(|_| remote::default_cache()))
+                .unwrap_or_else(|| match Path::new(DEFAULT_CORPUS).exists() {
+                    true => DEFAULT_CORPUS.to_string(),
+                    false => EMBEDDED.to_string(),
+                }),
+            url,
+            answers: env::var("ANSWERS_FILE").ok(),
+            alphabet: env::var("ALPHABET_FILE").ok(),
+            frequencies: env::var("FREQUENCY_FILE").ok(),
+            opening_book: env::var("OPENING_BOOK").ok(),
+            past_answers: env::var("PAST_ANSWERS_FILE").ok()
+                .or_else(|| Path::new(DEFAULT_PAST_ANSWERS).exists().then(|| DEFAULT_PAST_ANSWERS.to_string())),
+            proper_nouns: env::var("PROPER_NOUNS_FILE").ok(),
+            denylist: env::var("DENYLIST_FILE").ok(),
+            fold_diacritics: crate::env_parse("FOLD_DIACRITICS").unwrap_or(false),
+        }
+    }
+
+    /// `{lang}.txt` in `dir`, with `{lang}.answers.txt`, `{lang}.alphabet.txt`,
+    /// `{lang}.frequencies.txt`, `{lang}.openings.txt`,
+    /// `{lang}.past_answers.txt`, `{lang}.proper_nouns.txt` and
+    /// `{lang}.denylist.txt` when they exist, all
+    /// of them possibly compressed. Diacritics are folded for the languages
+    /// listed in the comma-separated `FOLD_DIACRITICS_LANGUAGES`.
+    fn in_dir(dir: &Path, lang: &str) -> Result<CorpusFiles, String> {
+        let file = |suffix: &str| {
+            let name = format!("{}{}.txt", lang, suffix);
+            [""].into_iter().chain(COMPRESSED)
+                .map(|extension| dir.join(format!("{}{}", name, extension)))
+                .find(|path| path.exists())
+                .map(|path| path.to_string_lossy().into_owned())
+        };
+        Ok(CorpusFiles {
+            corpus: file("").ok_or_else(|| format!("No corpus for language {:?}", lang))?,
+            url: None,
+            answers: file(".answers"),
+            alphabet: file(".alphabet"),
+            frequencies: file(".frequencies"),
+            opening_book: file(".openings"),
+            past_answers: file(".past_answers"),
+            proper_nouns: file(".proper_nouns"),
+            denylist: file(".denylist"),
+            fold_diacritics: env::var("FOLD_DIACRITICS_LANGUAGES").is_ok_and(|langs| langs.split(',').any(|l| l.trim() == lang)),
+        })
+    }
+}
+
+/// Every `{lang}.txt` of `dir`, read from `LANGUAGES_DIR`, by language.
+pub fn languages(dir: &str) -> Result<Vec<(String, CorpusFiles)>, String> {
+    let failed = |e: io::Error| format!("Failed to read languages directory {}: {}", dir, e);
+    let names = fs::read_dir(dir).map_err(failed)?
+        .map(|entry| entry.map(|entry| entry.file_name()).map_err(failed))
+        .collect::<Result<Vec<_>, _>>()?;
+    let dir = Path::new(dir);
+    names.into_iter()
+        .filter_map(|name| {
+            let name = name.to_str()?;
+            let name = COMPRESSED.iter().find_map(|extension| name.strip_suffix(extension)).unwrap_or(name);
+            let lang = name.strip_suffix(".txt")?;
+            (!lang.contains('.')).then(|| lang.to_string())
+        })
+        .sorted()
+        .dedup()
+        .map(|lang| {
+            let files = CorpusFiles::in_dir(dir, &lang)?;
+            Ok((lang, files))
+        })
+        .collect()
+}
+
+/// Every corpus as loaded at the same time.
+struct Loaded {
+    default: Arc<AppState>,
+    languages: HashMap<String, Arc<AppState>>,
+}
+
+impl Loaded {
+    fn read(files: &CorpusFiles, languages_dir: Option<&str>, settings: &Settings) -> Result<Loaded, String> {
+        Ok(Loaded {
+            default: Arc::new(crate::load_state(files, settings)?),
+            languages: languages_dir.map_or(Ok(vec![]), languages)?.into_iter()
+                .map(|(lang, files)| Ok((lang, Arc::new(crate::load_state(&files, settings)?))))
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+/// How much users may upload to `/api/corpora`, read from
+/// `UPLOAD_MAX_WORDS`, `UPLOAD_MAX_CORPORA` and `UPLOAD_TTL_SECONDS`.
+#[derive(Debug, Copy, Clone)]
+pub struct UploadLimits {
+    pub max_words: usize,
+    pub max_corpora: usize,
+    /// How long uploads are kept when they don't say; forever without it.
+    pub default_ttl: Option<Duration>,
+}
+
+const DEFAULT_UPLOAD_MAX_WORDS: usize = 100_000;
+const DEFAULT_UPLOAD_MAX_CORPORA: usize = 32;
+/// Bytes allowed per word of an upload, to bound its body before it is read.
+const UPLOAD_BYTES_PER_WORD: usize = 64;
+
+impl UploadLimits {
+    pub fn from_env() -> UploadLimits {
+        UploadLimits {
+            max_words: crate::env_parse("UPLOAD_MAX_WORDS").unwrap_or(DEFAULT_UPLOAD_MAX_WORDS),
+            max_corpora: crate::env_parse("UPLOAD_MAX_CORPORA").unwrap_or(DEFAULT_UPLOAD_MAX_CORPORA),
+            default_ttl: crate::env_parse("UPLOAD_TTL_SECONDS").map(Duration::from_secs),
+        }
+    }
+
+    /// The largest body `/api/corpora` reads, whatever `PAYLOAD_LIMIT` is.
+    pub fn max_bytes(&self) -> usize {
+        self.max_words.saturating_mul(UPLOAD_BYTES_PER_WORD)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CorpusQuery {
+    corpus: Option<String>,
+}
+
+/// A corpus posted to `/api/corpora`, kept in memory until it expires.
+struct Upload {
+    state: Arc<AppState>,
+    expires: Option<Instant>,
+}
+
+impl Upload {
+    fn live(&self, now: Instant) -> bool {
+        self.expires.is_none_or(|at| at > now)
+    }
+}
+
+/// The corpora requests can be answered from: the default one, one per
+/// language and one per upload, the latter two under `/api/{id}/...` or with
+/// `?corpus={id}`. Files
+/// can be reloaded from disk while serving: requests that already picked a
+/// corpus finish with it. Uploads are left as they are by a reload.
+pub struct Corpora {
+    files: CorpusFiles,
+    languages_dir: Option<String>,
+    loaded: ArcSwap<Loaded>,
+    uploads: Mutex<HashMap<String, Upload>>,
+    /// Held while reloading or editing, so that no change is lost.
+    writing: Mutex<()>,
+    pub upload_limits: UploadLimits,
+    /// What every corpus is indexed with, uploads and edits included.
+    pub settings: Settings,
+}
+
+impl Corpora {
+    pub fn load(files: CorpusFiles, languages_dir: Option<String>, settings: Settings) -> Result<Corpora, String> {
+        let loaded = Loaded::read(&files, languages_dir.as_deref(), &settings)?;
+        Ok(Corpora {
+            files,
+            languages_dir,
+            loaded: ArcSwap::from_pointee(loaded),
+            uploads: Mutex::new(HashMap::new()),
+            writing: Mutex::new(()),
+            upload_limits: UploadLimits::from_env(),
+            settings,
+        })
+    }
+
+    /// Makes `state` available as `id`, for `ttl` if given. Expired uploads
+    /// are dropped first, so that they don't count against the limit.
+    pub fn add_upload(&self, id: &str, state: AppState, ttl: Option<Duration>) -> Result<(), String> {
+        let now = Instant::now();
+        let mut uploads = self.uploads.lock().unwrap_or_else(PoisonError::into_inner);
+        uploads.retain(|_, upload| upload.live(now));
+        if uploads.contains_key(id) || self.loaded.load().languages.contains_key(id) {
+            return Err(format!("Corpus {:?} already exists", id));
+        }
+        if uploads.len() >= self.upload_limits.max_corpora {
+            return Err(format!("There are already {} uploaded corpora", uploads.len()));
+        }
+        let expires = ttl.map(|ttl| now + ttl);
+        uploads.insert(id.to_string(), Upload { state: Arc::new(state), expires });
+        Ok(())
+    }
+
+    fn upload(&self, id: &str) -> Option<Arc<AppState>> {
+        let now = Instant::now();
+        self.uploads.lock().unwrap_or_else(PoisonError::into_inner).get(id)
+            .filter(|upload| upload.live(now))
+            .map(|upload| upload.state.clone())
+    }
+
+    /// Reads every corpus again, languages added to or removed from
+    /// `LANGUAGES_DIR` included, and swaps them all in at once. When any of
+    /// them fails to load, the previous corpora are kept.
+    pub fn reload(&self) -> Result<(), String> {
+        let _writing = self.writing.lock().unwrap_or_else(PoisonError::into_inner);
+        self.loaded.store(Arc::new(Loaded::read(&self.files, self.languages_dir.as_deref(), &self.settings)?));
+        Ok(())
+    }
+
+    /// Reads the past answers of every corpus again, leaving the rest as it
+    /// is, and returns how many the default corpus has.
+    pub fn reload_past_answers(&self) -> Result<usize, String> {
+        let loaded = self.loaded.load();
+        for state in loaded.languages.values() {
+            state.past_answers.reload(&state.alphabet)?;
+        }
+        loaded.default.past_answers.reload(&loaded.default.alphabet)
+    }
+
+    /// Swaps in the corpus `edit` makes of the one of `language`, the default
+    /// one when `None`, which is given with the files it was read from.
+    pub fn edit(&self, language: Option<&str>, edit: impl FnOnce(&AppState, &CorpusFiles) -> actix_web::Result<AppState>) -> actix_web::Result<Arc<AppState>> {
+        let _writing = self.writing.lock().unwrap_or_else(PoisonError::into_inner);
+        let loaded = self.loaded.load();
+        let mut languages = loaded.languages.clone();
+        let edited = match (language, &self.languages_dir) {
+            (None, _) => Arc::new(edit(&loaded.default, &self.files)?),
+            (Some(lang), Some(dir)) if languages.contains_key(lang) => {
+                let files = CorpusFiles::in_dir(Path::new(dir), lang).map_err(bad_request)?;
+                let edited = Arc::new(edit(&languages[lang], &files)?);
+                languages.insert(lang.to_string(), edited.clone());
+                edited
+            },
+            (Some(lang), _) => return Err(bad_request(format!("Unknown language: {:?}", lang))),
+        };
+        let default = match language {
+            None => edited.clone(),
+            Some(_) => loaded.default.clone(),
+        };
+        self.loaded.store(Arc::new(Loaded { default, languages }));
+        Ok(edited)
+    }
+
+    pub fn default_state(&self) -> web::Data<AppState> {
+        web::Data::from(self.loaded.load().default.clone())
+    }
+
+    pub fn languages(&self) -> Vec<String> {
+        self.loaded.load().languages.keys().sorted().cloned().collect()
+    }
+
+    /// Every language corpus with its name, sorted by name.
+    pub fn language_states(&self) -> Vec<(String, Arc<AppState>)> {
+        self.loaded.load().languages.iter().map(|(name, state)| (name.clone(), state.clone())).sorted_by(|(a, _), (b, _)| a.cmp(b)).collect()
+    }
+
+    fn find(&self, loaded: &Loaded, id: &str) -> Option<Arc<AppState>> {
+        loaded.languages.get(id).cloned().or_else(|| self.upload(id))
+    }
+
+    /// Makes the corpus `req` asks for the `web::Data<AppState>` its handler
+    /// gets: the one named by the segment after `/api/`, else the one of the
+    /// `corpus` query parameter, else the default one. The segment is dropped
+    /// from the path, so that the request is routed like the same one to the
+    /// default corpus. Fails on an unknown `corpus`.
+    pub fn select(&self, req: &mut ServiceRequest, base_path: &str) -> actix_web::Result<()> {
+        let loaded = self.loaded.load();
+        let prefix = format!("{}/api/", base_path);
+        let from_path = req.path().strip_prefix(&prefix)
+            .and_then(|rest| rest.split_once('/'))
+            .and_then(|(id, rest)| self.find(&loaded, id).map(|state| (state, format!("{}{}", prefix, rest))));
+        let state = match from_path {
+            Some((state, path)) => {
+                rewrite_path(req, &path);
+                state
+            },
+            None => match web::Query::<CorpusQuery>::from_query(req.query_string()).ok().and_then(|q| q.into_inner().corpus) {
+                Some(id) => self.find(&loaded, &id).ok_or_else(|| bad_request(format!("Unknown corpus: {:?}", id)))?,
+                None => loaded.default.clone(),
+            },
+        };
+        let mut data = Extensions::new();
+        data.insert(web::Data::from(state));
+        req.add_data_container(Rc::new(data));
+        Ok(())
+    }
+}
+
+fn rewrite_path(req: &mut ServiceRequest, path: &str) {
+    let path_and_query = match req.query_string() {
+        "" => path.to_string(),
+        query => format!("{}?{}", path, query),
+    };
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = path_and_query.parse().ok();
+    if let Ok(uri) = Uri::from_parts(parts) {
+        req.match_info_mut().get_mut().update(&uri);
+        req.head_mut().uri = uri;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::CaseFolding;
+
+    fn files(corpus: &Path) -> CorpusFiles {
+        CorpusFiles {
+            corpus: corpus.to_string_lossy().into_owned(),
+            url: None,
+            answers: None,
+            alphabet: None,
+            frequencies: None,
+            opening_book: None,
+            past_answers: None,
+            proper_nouns: None,
+            denylist: None,
+            fold_diacritics: false,
+        }
+    }
+
+    fn words(corpora: &Corpora) -> Vec<String> {
+        corpora.default_state().corpus.iter().flat_map(|(_, words)| words.clone()).collect()
+    }
+
+    #[test]
+    fn failed_reload_keeps_the_previous_corpus() {
+        let dir = env::temp_dir().join(format!("wordler-reload-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let corpus = dir.join("corpus.txt");
+        fs::write(&corpus, "crane\nslate\n").unwrap();
+        let corpora = Corpora::load(files(&corpus), None, Settings { case: CaseFolding::Upper, ..Settings::from_env() }).unwrap();
+
+        fs::remove_file(&corpus).unwrap();
+        assert!(corpora.reload().is_err());
+        assert_eq!(words(&corpora), ["CRANE", "SLATE"]);
+
+        fs::write(&corpus, "crane\nslate\ntrace\n").unwrap();
+        corpora.reload().unwrap();
+        assert_eq!(words(&corpora), ["CRANE", "SLATE", "TRACE"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+use actix_web::{body::BoxBody, http::header::{self, Accept, Header}, web, HttpRequest, HttpResponse, Responder};
+use prost::Message;
+use serde::Serialize;
+
+use crate::proto;
+
+const MSGPACK: &str = "application/msgpack";
+const CBOR: &str = "application/cbor";
+const PROTOBUF: &str = "application/x-protobuf";
+
+/// Serializes the response as JSON, MessagePack, CBOR or (for types with a
+/// message in `proto/wordler.proto`) Protocol Buffers depending on the
+/// request's `Accept` header, defaulting to JSON when there is none. A
+/// request that accepts none of the encodings the type has gets a 406.
+pub struct Encoded<T>(pub T);
+
+/// Response types that have a protobuf representation override
+/// `encode_protobuf`; the rest are never offered as protobuf.
+pub trait Protobuf {
+    fn encode_protobuf(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+impl Protobuf for Vec<String> {
+    fn encode_protobuf(&self) -> Option<Vec<u8>> {
+        Some(proto::WordList { words: self.clone() }.encode_to_vec())
+    }
+}
+
+#[derive(Debug)]
+enum Encoding {
+    Json,
+    MsgPack,
+    Cbor,
+    Protobuf(Vec<u8>),
+    NotAcceptable,
+}
+
+fn negotiate<T: Protobuf>(req: &HttpRequest, value: &T) -> Encoding {
+    match Accept::parse(req) {
+        Ok(accept) if !accept.is_empty() => accept.ranked().iter()
+            .find_map(|mime| match mime.essence_str() {
+                MSGPACK | "application/x-msgpack" => Some(Encoding::MsgPack),
+                CBOR => Some(Encoding::Cbor),
+                PROTOBUF | "application/protobuf" => value.encode_protobuf().map(Encoding::Protobuf),
+                "application/json" | "application/*" | "*/*" => Some(Encoding::Json),
+                _ => None,
+            })
+            .unwrap_or(Encoding::NotAcceptable),
+        _ => Encoding::Json,
+    }
+}
+
+impl<T: Serialize + Protobuf> Responder for Encoded<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let encoded = match negotiate(req, &self.0) {
+            Encoding::Json => {
+                let mut res = web::Json(self.0).respond_to(req).map_into_boxed_body();
+                res.headers_mut().insert(header::VARY, header::HeaderValue::from_static("accept"));
+                return res;
+            },
+            Encoding::MsgPack => rmp_serde::to_vec_named(&self.0)
+                .map(|body| (MSGPACK, body))
+                .map_err(|e| e.to_string()),
+            Encoding::Cbor => {
+                let mut body = Vec::new();
+                ciborium::into_writer(&self.0, &mut body)
+                    .map(|_| (CBOR, body))
+                    .map_err(|e| e.to_string())
+            },
+            Encoding::Protobuf(body) => Ok((PROTOBUF, body)),
+            Encoding::NotAcceptable => return HttpResponse::NotAcceptable()
+                .insert_header((header::VARY, "accept"))
+                .body("None of the accepted types is available: responses are JSON, MessagePack, CBOR and, \
+                    for the types proto/wordler.proto has a message for, Protocol Buffers"),
+        };
+
+        match encoded {
+            Ok((content_type, body)) => HttpResponse::Ok()
+                .content_type(content_type)
+                .insert_header((header::VARY, "accept"))
+                .body(body),
+            Err(e) => HttpResponse::InternalServerError().body(e),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::Serialize;
+
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// A clue token that can't be parsed.
+    BadToken,
+    /// Words, tokens or patterns that should have the same length don't.
+    LengthMismatch,
+    /// Guesses that can't all hold at once.
+    ConflictingClues,
+    /// No word in the word list has the requested length.
+    UnknownLength,
+    /// Anything else the request got wrong.
+    BadRequest,
+    /// An admin route without the right token.
+    Unauthorized,
+    /// The corpora couldn't be reloaded; the previous ones are still served.
+    ReloadFailed,
+    /// A change couldn't be written to the corpus files; nothing was changed.
+    WriteFailed,
+}
+
+/// An error response with a `{code, message, detail}` JSON body, where
+/// `detail` holds the structured reason when there is one. Most codes are a
+/// 400.
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+    code: ErrorCode,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl ToString) -> Self {
+        ApiError { code, message: message.to_string(), detail: None }
+    }
+
+    pub fn with_detail(self, detail: impl Serialize) -> Self {
+        ApiError { detail: serde_json::to_value(detail).ok(), ..self }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self.code {
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::ReloadFailed | ErrorCode::WriteFailed => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+/// An error with the generic `bad_request` code.
+pub fn bad_request(message: impl ToString) -> actix_web::Error {
+    ApiError::new(ErrorCode::BadRequest, message).into()
+}
+
+/// An error with a specific code and no detail.
+pub fn error(code: ErrorCode, message: impl ToString) -> actix_web::Error {
+    ApiError::new(code, message).into()
+}
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use arc_swap::ArcSwap;
+use serde::Deserialize;
+
+use crate::{alphabet::Alphabet, corpora, AppState};
+
+/// Words that have already been the official answer, read from
+/// `PAST_ANSWERS_FILE`. The list grows every day, so it is kept apart from the
+/// rest of the corpus and can be read again on its own.
+#[derive(Default)]
+pub struct PastAnswers {
+    path: Option<String>,
+    words: ArcSwap<HashSet<String>>,
+}
+
+impl Clone for PastAnswers {
+    fn clone(&self) -> PastAnswers {
+        PastAnswers { path: self.path.clone(), words: ArcSwap::new(self.words.load_full()) }
+    }
+}
+
+fn read(alphabet: &Alphabet, path: &str) -> Result<HashSet<String>, String> {
+    let text = corpora::read_file(path).map_err(|e| format!("Failed to read past answers file {}: {}", path, e))?;
+    Ok(crate::clean_words(alphabet, path, &text).into_iter().collect())
+}
+
+impl PastAnswers {
+    pub fn load(alphabet: &Alphabet, path: Option<String>) -> Result<PastAnswers, String> {
+        let words = path.as_deref().map_or(Ok(HashSet::new()), |path| read(alphabet, path))?;
+        Ok(PastAnswers { path, words: ArcSwap::from_pointee(words) })
+    }
+
+    /// Reads the file again, if there is one, and returns how many words it
+    /// has. The previous list is kept when the file can't be read.
+    pub fn reload(&self, alphabet: &Alphabet) -> Result<usize, String> {
+        if let Some(path) = &self.path {
+            self.words.store(read(alphabet, path)?.into());
+        }
+        Ok(self.words.load().len())
+    }
+}
+
+/// Words to leave out of the candidates, read from the query string.
+#[derive(Debug, Default, Deserialize)]
+pub struct ExcludeQuery {
+    /// Skip the words that have already been the official answer.
+    #[serde(default)]
+    exclude_past: bool,
+    /// Skip plurals: words ending in a single `S`, or in `ES`, that are
+    /// another word of the corpus with that ending added.
+    #[serde(default)]
+    exclude_plurals: bool,
+    /// Skip the words `PROPER_NOUNS_FILE` lists.
+    #[serde(default)]
+    exclude_proper_nouns: bool,
+    /// Never return the words `DENYLIST_FILE` lists, neither as candidates nor
+    /// as guesses.
+    #[serde(default)]
+    safe: bool,
+}
+
+/// Whether `word` looks like the plural of another word of the corpus. Only
+/// meant for English-like corpora.
+fn is_plural(state: &AppState, word: &str) -> bool {
+    let s = state.alphabet.fold_letter('s');
+    let e = state.alphabet.fold_letter('e');
+    let chars = word.chars().collect::<Vec<_>>();
+    let stem = |len: usize| crate::is_word(state, &chars[..len].iter().collect::<String>());
+    match chars.as_slice() {
+        [.., a, b] if *a == s && *b == s => false,
+        [.., a, b] if *a == e && *b == s => stem(chars.len() - 1) || stem(chars.len() - 2),
+        [.., b] if *b == s => stem(chars.len() - 1),
+        _ => false,
+    }
+}
+
+impl ExcludeQuery {
+    pub fn apply<'a>(&self, state: &AppState, candidates: Vec<&'a str>) -> Vec<&'a str> {
+        let past = state.past_answers.words.load();
+        candidates.into_iter()
+            .filter(|w| !(self.exclude_past && past.contains(*w)))
+            .filter(|w| !(self.exclude_proper_nouns && state.proper_nouns.contains(*w)))
+            .filter(|w| !(self.exclude_plurals && is_plural(state, w)))
+            .filter(|w| self.allows(state, w))
+            .collect()
+    }
+
+    /// Whether `word` may be suggested as a guess.
+    pub fn allows(&self, state: &AppState, word: &str) -> bool {
+        !(self.safe && state.denylist.contains(word))
+    }
+
+    /// Whether guesses are filtered at all, for caches keyed by query.
+    pub fn safe(&self) -> bool {
+        self.safe
+    }
+}
@@ -0,0 +1,83 @@
+use std::env;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+use crate::error::bad_request;
+
+/// Bumped whenever what the signature covers changes, so that old exports are
+/// refused instead of misread.
+const EXPORT_VERSION: u8 = 1;
+
+/// A game in progress as a self-contained blob that `/api/import` takes back,
+/// on this instance or any other sharing its `EXPORT_KEY`.
+#[derive(Serialize, Deserialize)]
+pub struct GameExport {
+    pub version: u8,
+    /// The guesses so far, as the pattern they were exported from.
+    pub pattern: String,
+    /// The `/api/state` token of those guesses.
+    pub state: String,
+    /// HMAC-SHA256 of the version, state and pattern, in unpadded base64url.
+    pub signature: String,
+}
+
+/// The key exports are signed with, from `EXPORT_KEY`. Without it export and
+/// import are disabled.
+pub struct ExportKey(Option<hmac::Key>);
+
+impl ExportKey {
+    pub fn from_env() -> ExportKey {
+        ExportKey(env::var("EXPORT_KEY").ok()
+            .filter(|k| !k.is_empty())
+            .map(|k| hmac::Key::new(hmac::HMAC_SHA256, k.as_bytes())))
+    }
+
+    fn key(&self) -> actix_web::Result<&hmac::Key> {
+        self.0.as_ref().ok_or_else(|| bad_request("Game export is disabled, set EXPORT_KEY to enable it"))
+    }
+
+    pub fn export(&self, pattern: String, state: String) -> actix_web::Result<GameExport> {
+        let tag = hmac::sign(self.key()?, &signed(EXPORT_VERSION, &state, &pattern));
+        Ok(GameExport { version: EXPORT_VERSION, pattern, state, signature: URL_SAFE_NO_PAD.encode(tag) })
+    }
+
+    /// Fails unless `game` was exported with this key.
+    pub fn verify(&self, game: &GameExport) -> actix_web::Result<()> {
+        let key = self.key()?;
+        if game.version != EXPORT_VERSION {
+            return Err(bad_request("Unsupported game export version"));
+        }
+        let signature = URL_SAFE_NO_PAD.decode(&game.signature).map_err(|_| bad_request("Invalid game export signature"))?;
+        hmac::verify(key, &signed(game.version, &game.state, &game.pattern), &signature)
+            .map_err(|_| bad_request("Invalid game export signature"))
+    }
+}
+
+/// The bytes a signature covers. State tokens are base64url, so the `:` after
+/// them can't be moved into the pattern.
+fn signed(version: u8, state: &str, pattern: &str) -> Vec<u8> {
+    format!("{}:{}:{}", version, state, pattern).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(secret: &str) -> ExportKey {
+        ExportKey(Some(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())))
+    }
+
+    #[test]
+    fn exports_verify_only_with_their_key_and_contents() {
+        let game = key("secret").export("CRANE01000".to_string(), "AQ".to_string()).unwrap();
+        assert!(key("secret").verify(&game).is_ok());
+        assert!(key("other").verify(&game).is_err());
+        assert!(key("secret").verify(&GameExport { pattern: "CRANE22222".to_string(), ..game }).is_err());
+    }
+
+    #[test]
+    fn export_is_disabled_without_a_key() {
+        assert!(ExportKey(None).export("CRANE01000".to_string(), "AQ".to_string()).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+/// What one tile of a feedback row says about its letter. A variant with
+/// another kind of feedback adds its tile here, a score for it in the parser,
+/// and what it implies in `extract_clue`; grading, filtering and scoring only
+/// deal with tiles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tile {
+    /// Not in the word, or no unmatched copies of it left.
+    Absent,
+    /// In the word, elsewhere.
+    Misplaced,
+    Correct,
+}
+
+impl Tile {
+    pub fn digit(self) -> char {
+        match self {
+            Tile::Absent => '0',
+            Tile::Misplaced => '1',
+            Tile::Correct => '2',
+        }
+    }
+
+    pub fn from_digit(c: char) -> Option<Tile> {
+        match c {
+            '0' => Some(Tile::Absent),
+            '1' => Some(Tile::Misplaced),
+            '2' => Some(Tile::Correct),
+            _ => None,
+        }
+    }
+}
+
+/// Grades `guess` against `answer` the way Wordle does: exact matches first,
+/// then each remaining guess letter is marked misplaced only while unmatched
+/// copies of it are left in the answer.
+pub fn grade(answer: &str, guess: &str) -> Vec<Tile> {
+    grade_chars(&answer.chars().collect::<Vec<_>>(), &guess.chars().collect::<Vec<_>>())
+}
+
+pub fn grade_chars(answer: &[char], guess: &[char]) -> Vec<Tile> {
+    let mut result = vec![Tile::Absent; guess.len()];
+    let mut unmatched: HashMap<char, usize> = HashMap::new();
+    for (i, a) in answer.iter().enumerate() {
+        if guess.get(i) == Some(a) {
+            result[i] = Tile::Correct;
+        } else {
+            *unmatched.entry(*a).or_default() += 1;
+        }
+    }
+    for (i, g) in guess.iter().enumerate() {
+        if result[i] == Tile::Correct {
+            continue;
+        }
+        if let Some(n) = unmatched.get_mut(g).filter(|n| **n > 0) {
+            *n -= 1;
+            result[i] = Tile::Misplaced;
+        }
+    }
+    result
+}
+
+pub fn solved(feedback: &[Tile]) -> bool {
+    feedback.iter().all(|t| *t == Tile::Correct)
+}
+
+pub fn to_digits(feedback: &[Tile]) -> String {
+    feedback.iter().map(|t| t.digit()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_letters() {
+        let cases = [
+            // One E in the answer: only the first E of the guess is yellow.
+            ("abide", "speed", "00101"),
+            // The green E uses up a copy before any yellow is given.
+            ("plane", "eagle", "01012"),
+            ("there", "eerie", "10102"),
+            ("eerie", "every", "20110"),
+            ("eerie", "eerie", "22222"),
+            // More copies in the answer than in the guess.
+            ("eerie", "crepe", "01102"),
+            // Three Ls against two: one green, one yellow, one gray.
+            ("knoll", "lolly", "11020"),
+        ];
+        for (answer, guess, feedback) in cases {
+            assert_eq!(to_digits(&grade(answer, guess)), feedback, "{} against {}", guess, answer);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Adjacency lists over the words of a single length: `neighbors[i]` holds the
+/// indices of every word that differs from word `i` in exactly one letter.
+pub type Neighbors = Vec<Vec<usize>>;
+
+pub fn build_neighbors(words: &[String]) -> Neighbors {
+    let mut buckets: HashMap<(usize, String), Vec<usize>> = HashMap::new();
+    for (idx, word) in words.iter().enumerate() {
+        let chars = word.chars().collect::<Vec<_>>();
+        for pos in 0..chars.len() {
+            let key = chars.iter().enumerate()
+                .map(|(i, c)| if i == pos { '*' } else { *c })
+                .collect::<String>();
+            buckets.entry((pos, key)).or_default().push(idx);
+        }
+    }
+
+    let mut neighbors = vec![Vec::new(); words.len()];
+    for bucket in buckets.values() {
+        for &a in bucket {
+            neighbors[a].extend(bucket.iter().filter(|&&b| b != a));
+        }
+    }
+    neighbors
+}
+
+pub fn shortest_ladder(neighbors: &Neighbors, from: usize, to: usize) -> Option<Vec<usize>> {
+    let mut previous = vec![None; neighbors.len()];
+    let mut queue = VecDeque::from([from]);
+    previous[from] = Some(from);
+
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![to];
+            while let Some(&last) = path.last() {
+                if last == from {
+                    break;
+                }
+                path.push(previous[last]?);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &next in neighbors[current].iter() {
+            if previous[next].is_none() {
+                previous[next] = Some(current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use crate::alphabet::Alphabet;
+
+/// How often each letter appears in a word list, as a share of all letters.
+pub type Profile = HashMap<char, f64>;
+
+pub fn profile<'a>(alphabet: &Alphabet, words: impl Iterator<Item = &'a str>) -> Profile {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for word in words {
+        for c in alphabet.word(word).chars() {
+            *counts.entry(c).or_default() += 1;
+        }
+    }
+    let total = counts.values().sum::<usize>().max(1) as f64;
+    counts.into_iter().map(|(c, n)| (c, n as f64 / total)).collect()
+}
+
+/// The cosine similarity of two profiles, from 0 when they share no letter to
+/// 1 when the letters are used in the same proportions.
+pub fn similarity(a: &Profile, b: &Profile) -> f64 {
+    let dot = a.iter().map(|(c, x)| x * b.get(c).unwrap_or(&0.0)).sum::<f64>();
+    let norm = |p: &Profile| p.values().map(|x| x * x).sum::<f64>().sqrt();
+    match norm(a) * norm(b) {
+        0.0 => 0.0,
+        n => dot / n,
+    }
+}
+
+/// The language whose corpus uses letters most like `words` do, among those
+/// whose alphabet spells every one of them, with its similarity.
+pub fn detect<'a>(words: &[&str], languages: impl Iterator<Item = (&'a str, &'a Alphabet, &'a Profile)>) -> Option<(&'a str, f64)> {
+    languages
+        .filter(|(_, alphabet, _)| words.iter().all(|w| alphabet.word(w).chars().all(|c| alphabet.is_letter(c))))
+        .map(|(name, alphabet, corpus)| (name, similarity(&profile(alphabet, words.iter().copied()), corpus)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn alphabet(letters: &str) -> Alphabet {
+        Alphabet::parse(&letters.chars().map(String::from).join("\n"), Default::default()).unwrap()
+    }
+
+    #[test]
+    fn picks_the_language_with_the_closest_letters() {
+        let english = alphabet("abcdefghijklmnopqrstuvwxyz");
+        let spanish = alphabet("abcdefghijklmnñopqrstuvwxyz");
+        let english_profile = profile(&english, ["the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog", "with", "thing"].into_iter());
+        let spanish_profile = profile(&spanish, ["el", "perro", "come", "una", "manzana", "casa", "mañana", "queso", "dos", "para"].into_iter());
+        let languages = || [("en", &english, &english_profile), ("es", &spanish, &spanish_profile)].into_iter();
+        let detected = |words: &[&str]| detect(words, languages()).map(|(language, _)| language);
+
+        assert_eq!(detected(&["nada", "cama", "ropa", "mesa"]), Some("es"));
+        assert_eq!(detected(&["thought", "throw", "which", "wish"]), Some("en"));
+        // Only the Spanish alphabet has Ñ.
+        assert_eq!(detected(&["thought", "niño"]), Some("es"));
+        assert_eq!(detected(&["garçon"]), None);
+    }
+}
@@ -1,12 +1,142 @@
-use std::{fs, iter::zip, env};
-use regex::Regex;
-use itertools::Itertools;
+mod admin;
+mod alphabet;
+mod audit;
+mod cluster;
+mod corpora;
+mod encoding;
+mod error;
+mod exclusions;
+mod export;
+mod feedback;
+mod ladder;
+mod language;
+mod openers;
+mod optimal;
+mod parser;
+mod per_length;
+mod proto;
+mod remote;
+mod scoring;
+mod tiebreak;
+mod tree;
+mod share;
+mod snapshot;
+mod strategy;
+mod wordset;
 
-use actix_web::{get, web, App, error::ErrorBadRequest, HttpServer, Responder, Result};
+use std::{cmp::Reverse, collections::{HashMap, HashSet}, iter::{repeat_n, zip}, env, net::SocketAddr, str::FromStr, sync::{Arc, Mutex, OnceLock, PoisonError}, time::{Duration, Instant}};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use regex::RegexBuilder;
+use itertools::{EitherOrBoth, Itertools};
+use serde::{Deserialize, Serialize};
+use encoding::{Encoded, Protobuf};
+use admin::AdminToken;
+use audit::AuditLog;
+use prost::Message;
+use error::{bad_request, error, ApiError, ErrorCode};
+use scoring::{letter_counts, get_frequency, split_score, GuessScore};
+use strategy::{StrategyKind, StrategyQuery};
+use tiebreak::TieBreak;
+use wordset::WordSet;
+use alphabet::{Alphabet, CaseFolding};
+use corpora::{Corpora, CorpusFiles};
+use exclusions::{ExcludeQuery, PastAnswers};
+use export::{ExportKey, GameExport};
+use feedback::Tile;
+use per_length::PerLength;
+use parser::{canonical_token, color_digits, extract_answer, split_tokens, LetterAnswer, LetterAnswerType, TokenError, WordAnswer};
+
+use actix_web::{dev::Service, delete, get, post, put, web, App, Either, HttpRequest, HttpServer, Responder, Result};
+use actix_web::{http::header::{CacheControl, CacheDirective}, CustomizeResponder};
 
 struct AppState {
+    /// Every word that may be guessed, answers included.
     corpus: Vec<(usize, Vec<String>)>,
-    most_common: Vec<(usize, Vec<(char, usize)>)>,
+    alphabet: Alphabet,
+    /// The words that may be the answer; the whole corpus unless
+    /// `ANSWERS_FILE` is set.
+    answers: Vec<(usize, Vec<String>)>,
+    /// Whether the answers were read from their own file, and so don't grow
+    /// with the corpus.
+    separate_answers: bool,
+    most_common: PerLength<Vec<(char, usize)>>,
+    positional: PerLength<Vec<HashMap<char, usize>>>,
+    word_ids: PerLength<HashMap<String, usize>>,
+    answer_sets: PerLength<HashSet<String>>,
+    neighbors: PerLength<ladder::Neighbors>,
+    /// How often each letter appears in the corpus, to tell which language
+    /// an uploaded word list is in.
+    profile: language::Profile,
+    opening_book: HashMap<String, Vec<String>>,
+    frequencies: HashMap<String, u64>,
+    past_answers: PastAnswers,
+    /// Names of people, places and brands, from `PROPER_NOUNS_FILE`.
+    proper_nouns: HashSet<String>,
+    /// Offensive words, from `DENYLIST_FILE`.
+    denylist: HashSet<String>,
+    openers: OpenerCache,
+    subtrees: Mutex<tree::Subtrees>,
+    tree_max_nodes: usize,
+    slow_query: Option<Duration>,
+    lookahead_candidates: usize,
+    costs_candidates: usize,
+    valid_max_words: usize,
+    optimal_candidates: usize,
+    optimal_depth: usize,
+    optimal_time_limit: Duration,
+    static_max_age: Option<u32>,
+    dynamic_max_age: Option<u32>,
+}
+
+impl AppState {
+    /// How many answers of length `n` have each letter.
+    fn most_common(&self, n: usize) -> Option<&Vec<(char, usize)>> {
+        self.most_common.get(n, || letter_counts(length_words(&self.answers, n)))
+    }
+
+    fn positional(&self, n: usize) -> Option<&Vec<HashMap<char, usize>>> {
+        self.positional.get(n, || positional_counts(n, length_words(&self.answers, n)))
+    }
+
+    fn word_ids(&self, n: usize) -> Option<&HashMap<String, usize>> {
+        self.word_ids.get(n, || length_words(&self.corpus, n).iter().enumerate().map(|(id, w)| (w.clone(), id)).collect())
+    }
+
+    fn answer_set(&self, n: usize) -> Option<&HashSet<String>> {
+        self.answer_sets.get(n, || length_words(&self.answers, n).iter().cloned().collect())
+    }
+
+    fn neighbors(&self, n: usize) -> Option<&ladder::Neighbors> {
+        self.neighbors.get(n, || ladder::build_neighbors(length_words(&self.corpus, n)))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum RouteClass {
+    Static,
+    Dynamic,
+}
+
+fn cached<R: Responder>(state: &AppState, class: RouteClass, responder: R) -> CustomizeResponder<R> {
+    let max_age = match class {
+        RouteClass::Static => state.static_max_age,
+        RouteClass::Dynamic => state.dynamic_max_age,
+    };
+    match max_age {
+        Some(age) => responder.customize()
+            .insert_header(CacheControl(vec![CacheDirective::Public, CacheDirective::MaxAge(age)])),
+        None => responder.customize(),
+    }
+}
+
+/// Logs requests slower than `SLOW_QUERY_MS`, with the strategy they played
+/// if they take one.
+fn log_slow_query(state: &AppState, endpoint: &str, strategy: Option<StrategyKind>, pattern: &str, candidates: usize, start: Instant) {
+    let elapsed = start.elapsed();
+    if state.slow_query.is_some_and(|threshold| elapsed >= threshold) {
+        let strategy = strategy.map_or(String::new(), |s| format!(" strategy={:?}", s));
+        eprintln!("slow query: endpoint={}{} pattern={:?} candidates={} elapsed={:?}", endpoint, strategy, pattern, candidates, elapsed);
+    }
 }
 
 fn get_words<T>(corpus: &[(usize, T)], length: usize) -> Option<&T> {
@@ -15,20 +145,22 @@ fn get_words<T>(corpus: &[(usize, T)], length: usize) -> Option<&T> {
         .map(|(_, w)| w)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum WordCluePattern {
     Letter(char),
     Exclude(Vec<char>),
 }
 
-#[derive(Debug, Copy, Clone)]
+/// How many copies of `letter` the word has: at least `min`, and at most
+/// `max` when known.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 struct WordClueLetter {
     letter: char,
-    count: usize,
-    exact: bool,
+    min: usize,
+    max: Option<usize>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct WordClue {
     pattern: Vec<WordCluePattern>,
     letters: Vec<WordClueLetter>,
@@ -38,33 +170,36 @@ fn merge_letter_clue(map: &mut Vec<WordClueLetter>, clue: WordClueLetter) {
     if let Some((idx, w)) = map.iter().enumerate().find(|(_, WordClueLetter{ letter: l, ..})| *l == clue.letter) {
         map[idx] = WordClueLetter {
             letter: w.letter,
-            count: (w.count).max(clue.count),
-            exact: w.exact || clue.exact,
+            min: w.min.max(clue.min),
+            max: match (w.max, clue.max) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
         };
     } else {
         map.push(clue);
     }
 }
 
-fn merge(a: WordClue, b: WordClue) -> Result<WordClue, String> {
+fn merge(a: WordClue, b: WordClue) -> Result<WordClue, ApiError> {
     Ok(WordClue {
         pattern: {
             if a.pattern.len() != b.pattern.len() {
-                return Err(format!("Pattern length mismatch: {} != {}", a.pattern.len(), b.pattern.len()));
+                return Err(ApiError::new(ErrorCode::LengthMismatch, format!("Pattern length mismatch: {} != {}", a.pattern.len(), b.pattern.len())));
             }
-            zip(a.pattern.into_iter(), b.pattern.into_iter()).map(|(a, b)|{
+            zip(a.pattern, b.pattern).map(|(a, b)|{
                 match (a, b) {
                     (WordCluePattern::Letter(a), WordCluePattern::Letter(b)) => {
                         if a == b {
                             Ok(WordCluePattern::Letter(a))
                         } else {
-                            Err(format!("Conflict: {} != {}", a, b))
+                            Err(ApiError::new(ErrorCode::ConflictingClues, format!("Conflict: {} != {}", a, b)))
                         }
                     },
                     (WordCluePattern::Letter(a), _) => Ok(WordCluePattern::Letter(a)),
                     (_, WordCluePattern::Letter(b)) => Ok(WordCluePattern::Letter(b)),
                     (WordCluePattern::Exclude(a), WordCluePattern::Exclude(b)) => {
-                        Ok(WordCluePattern::Exclude(a.into_iter().chain(b.into_iter()).sorted().dedup().collect()))
+                        Ok(WordCluePattern::Exclude(a.into_iter().chain(b).sorted().dedup().collect()))
                     },
                 }
             }).collect::<Result<Vec<_>,_>>()?
@@ -79,46 +214,41 @@ fn merge(a: WordClue, b: WordClue) -> Result<WordClue, String> {
     })
 }
 
-#[derive(Debug)]
-enum LetterAnswerType {
-    Correct,
-    Incorrect,
-    NotInWord,
-}
-
-#[derive(Debug)]
-struct LetterAnswer {
-    letter: char,
-    answer: LetterAnswerType,
-}
-
-type WordAnswer = Vec<LetterAnswer>;
-
 fn extract_clue(word: WordAnswer) -> WordClue {
     let mut pattern: Vec<WordCluePattern> = Vec::new();
     let mut letter_clues: Vec<WordClueLetter> = Vec::new();
     let mut exclude: Vec<char> = Vec::new();
 
-    for LetterAnswer{letter, answer} in word.into_iter() {
+    for LetterAnswer{letter, answer, ..} in word.into_iter() {
         match answer {
-            LetterAnswerType::Correct => {
+            LetterAnswerType::Graded(Tile::Correct) => {
                 pattern.push(WordCluePattern::Letter(letter));
                 match letter_clues.iter().enumerate().find(|(_, WordClueLetter{ letter: l, ..})| *l == letter) {
-                    Some((idx, _)) => letter_clues[idx].count += 1,
-                    None => letter_clues.push(WordClueLetter{ letter, count: 1, exact: false }),
+                    Some((idx, _)) => letter_clues[idx].min += 1,
+                    None => letter_clues.push(WordClueLetter{ letter, min: 1, max: None }),
                 };
             },
-            LetterAnswerType::Incorrect => {
+            LetterAnswerType::Graded(Tile::Misplaced) => {
                 pattern.push(WordCluePattern::Exclude(vec![letter]));
                 match letter_clues.iter().enumerate().find(|(_, WordClueLetter{ letter: l, ..})| *l == letter) {
-                    Some((idx, _)) => letter_clues[idx].count += 1,
-                    None => letter_clues.push(WordClueLetter{ letter, count: 1, exact: false }),
+                    Some((idx, _)) => letter_clues[idx].min += 1,
+                    None => letter_clues.push(WordClueLetter{ letter, min: 1, max: None }),
                 };
             },
-            LetterAnswerType::NotInWord => {
+            LetterAnswerType::Graded(Tile::Absent) => {
                 pattern.push(WordCluePattern::Exclude(vec![letter]));
                 exclude.push(letter);
             },
+            LetterAnswerType::Present => {
+                pattern.push(WordCluePattern::Exclude(vec![]));
+                match letter_clues.iter().enumerate().find(|(_, WordClueLetter{ letter: l, ..})| *l == letter) {
+                    Some((idx, _)) => letter_clues[idx].min += 1,
+                    None => letter_clues.push(WordClueLetter{ letter, min: 1, max: None }),
+                };
+            },
+            LetterAnswerType::Unknown => {
+                pattern.push(WordCluePattern::Exclude(vec![]));
+            },
         }
     }
 
@@ -126,7 +256,7 @@ fn extract_clue(word: WordAnswer) -> WordClue {
         let mut result = Vec::new();
         for e in exclude.into_iter() {
             match letter_clues.iter().enumerate().find(|(_, WordClueLetter{ letter: l, ..})| l == &e) {
-                Some((idx, _)) => letter_clues[idx].exact = true,
+                Some((idx, _)) => letter_clues[idx].max = Some(letter_clues[idx].min),
                 None => result.push(e),
             };
         }
@@ -145,168 +275,3042 @@ fn extract_clue(word: WordAnswer) -> WordClue {
 
 }
 
-fn extract_answer(token: &str) -> Result<WordAnswer, String> {
-    let re = Regex::new(r"^([A-Za-zçÇ]+)([0-2]+)$").unwrap();
+/// Compares letters directly rather than through a regex, so that whatever
+/// letters a clue holds can't break the pattern.
+fn matcher(clue: &WordClue) -> impl Fn(&str) -> bool {
+    let pattern = clue.pattern.clone();
+    let letters = clue.letters.clone();
+    let fits = move |w: &str| w.chars().count() == pattern.len() && zip(&pattern, w.chars()).all(|(p, c)| match p {
+        WordCluePattern::Letter(l) => *l == c,
+        WordCluePattern::Exclude(v) => !v.contains(&c),
+    });
+    move |w| fits(w) && letters.iter().all(|l| {
+        let found = w.chars().filter(|c| *c == l.letter).count();
+        found >= l.min && l.max.is_none_or(|max| found <= max)
+    })
+}
+
+/// A clue constraint that a word breaks. Positions count from 1.
+#[derive(Debug, Serialize)]
+#[serde(tag = "constraint", rename_all = "snake_case")]
+enum Violation {
+    Length { expected: usize, found: usize },
+    Position { position: usize, expected: char, found: char },
+    Excluded { position: usize, letter: char },
+    Count { letter: char, min: usize, max: Option<usize>, found: usize },
+}
+
+/// Every constraint of the clue that `word` breaks; none if it matches.
+fn violations(clue: &WordClue, word: &str) -> Vec<Violation> {
+    let chars = word.chars().collect::<Vec<_>>();
+    if chars.len() != clue.pattern.len() {
+        return vec![Violation::Length { expected: clue.pattern.len(), found: chars.len() }];
+    }
+    let positions = zip(&clue.pattern, &chars).enumerate().filter_map(|(i, (p, c))| match p {
+        WordCluePattern::Letter(l) if l != c => Some(Violation::Position { position: i + 1, expected: *l, found: *c }),
+        WordCluePattern::Exclude(v) if v.contains(c) => Some(Violation::Excluded { position: i + 1, letter: *c }),
+        _ => None,
+    });
+    let counts = clue.letters.iter().filter_map(|l| {
+        let found = chars.iter().filter(|c| **c == l.letter).count();
+        let broken = found < l.min || l.max.is_some_and(|max| found > max);
+        broken.then_some(Violation::Count { letter: l.letter, min: l.min, max: l.max, found })
+    });
+    positions.chain(counts).collect()
+}
 
-    let (_, [letters, numbers]) = re.captures(token).map(|caps| caps.extract())
-        .ok_or(format!("Invalid token: {:?}", token))?;
+/// Two guesses of a clue set that can't both hold. Guesses and positions count
+/// from 1; `guess` is the one stating a fact that `other` rules out.
+#[derive(Debug, Serialize)]
+#[serde(tag = "conflict", rename_all = "snake_case")]
+enum Conflict {
+    Length { guess: usize, other: usize, length: usize, other_length: usize },
+    Position { guess: usize, other: usize, position: usize, letter: char, other_letter: char },
+    Excluded { guess: usize, other: usize, position: usize, letter: char },
+    Count { guess: usize, other: usize, letter: char, required: usize, allowed: usize },
+}
 
-    if letters.is_empty() {
-        return Err(format!("Invalid token: {:?}", token));
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conflict::Length { guess, other, length, other_length } =>
+                write!(f, "Guess {} has {} letters but guess {} has {}", guess, length, other, other_length),
+            Conflict::Position { guess, other, position, letter, other_letter } =>
+                write!(f, "Guess {} puts {} at position {} but guess {} puts {} there", guess, letter, position, other, other_letter),
+            Conflict::Excluded { guess, other, position, letter } =>
+                write!(f, "Guess {} puts {} at position {} but guess {} rules it out there", guess, letter, position, other),
+            Conflict::Count { guess, other, letter, required, allowed } =>
+                write!(f, "Guess {} needs at least {} {} but guess {} allows at most {}", guess, required, letter, other, allowed),
+        }
     }
+}
 
-    let letters = letters.to_uppercase().chars().collect::<Vec<_>>();
-    let numbers = numbers.chars().map(|c| { match c {
-        '0' => LetterAnswerType::NotInWord,
-        '1' => LetterAnswerType::Incorrect,
-        _   => LetterAnswerType::Correct,
-    }}).collect::<Vec<_>>();
-    if letters.len() != numbers.len() {
-        return Err(format!("Invalid token: {:?}, there are {} letters and {} numbers", token, letters.len(), numbers.len()));
+/// How many copies of `letter` the clue allows at most, if it says.
+fn allowed_count(clue: &WordClue, letter: char) -> Option<usize> {
+    match clue.letters.iter().find(|l| l.letter == letter) {
+        Some(l) => l.max,
+        None => clue.pattern.iter()
+            .all(|p| matches!(p, WordCluePattern::Exclude(v) if v.contains(&letter)))
+            .then_some(0),
     }
+}
 
-    Ok(zip(letters.into_iter(), numbers.into_iter()).map(|(letter, answer)| LetterAnswer{ letter, answer }).collect())
+/// Every pair of guesses in `clues`, in order, whose constraints contradict
+/// each other.
+fn conflicts(clues: &[WordClue]) -> Vec<Conflict> {
+    let mut found = Vec::new();
+    for ((i, a), (j, b)) in clues.iter().enumerate().cartesian_product(clues.iter().enumerate()) {
+        if i == j {
+            continue;
+        }
+        let (guess, other) = (i + 1, j + 1);
+        if a.pattern.len() != b.pattern.len() {
+            if i < j {
+                found.push(Conflict::Length { guess, other, length: a.pattern.len(), other_length: b.pattern.len() });
+            }
+            continue;
+        }
+        for (position, pair) in zip(&a.pattern, &b.pattern).enumerate() {
+            let position = position + 1;
+            match pair {
+                (WordCluePattern::Letter(x), WordCluePattern::Letter(y)) if x != y && i < j =>
+                    found.push(Conflict::Position { guess, other, position, letter: *x, other_letter: *y }),
+                (WordCluePattern::Letter(x), WordCluePattern::Exclude(v)) if v.contains(x) =>
+                    found.push(Conflict::Excluded { guess, other, position, letter: *x }),
+                _ => {},
+            }
+        }
+        for l in &a.letters {
+            if let Some(allowed) = allowed_count(b, l.letter).filter(|allowed| l.min > *allowed) {
+                found.push(Conflict::Count { guess, other, letter: l.letter, required: l.min, allowed });
+            }
+        }
+    }
+    found
 }
 
 fn filter<'a, T: AsRef<str>>(clue: &WordClue, words: &'a [T]) -> Vec<&'a str> {
+    let matches = matcher(clue);
+    words.iter().map(|w| w.as_ref()).filter(|w| matches(w)).collect()
+}
+
+fn parse_token(alphabet: &Alphabet, token: &str) -> Result<WordClue> {
+    extract_answer(alphabet, token)
+        .map(extract_clue)
+        .map_err(|e| ApiError::from(e).into())
+}
+
+/// The clue of every token of `pattern`; when some are malformed, the error
+/// lists all of them.
+fn parse_clues(alphabet: &Alphabet, pattern: &str) -> Result<Vec<WordClue>> {
+    let (clues, errors): (Vec<_>, Vec<_>) = split_tokens(pattern).iter().enumerate()
+        .map(|(i, token)| extract_answer(alphabet, token).map(extract_clue).map_err(|error| TokenError { guess: i + 1, error }))
+        .partition_result();
+    match errors.is_empty() {
+        true => Ok(clues),
+        false => Err(ApiError::from(errors).into()),
+    }
+}
+
+fn parse_clue(alphabet: &Alphabet, pattern: &str) -> Result<WordClue> {
+    merge_all(parse_clues(alphabet, pattern)?)
+}
+
+/// Merges the clues of every guess into one, failing with the conflicts found
+/// when some guesses contradict each other.
+fn merge_all(clues: Vec<WordClue>) -> Result<WordClue> {
+    let found = conflicts(&clues);
+    if let Some(first) = found.first() {
+        let code = match first {
+            Conflict::Length { .. } => ErrorCode::LengthMismatch,
+            _ => ErrorCode::ConflictingClues,
+        };
+        return Err(ApiError::new(code, found.iter().join("; ")).with_detail(found).into());
+    }
+    let mut rest = clues;
+    let mut result = rest.pop().ok_or(bad_request("Empty pattern"))?;
+    for clue in rest.into_iter() {
+        result = merge(result, clue)?;
+    }
+    Ok(result)
+}
+
+fn length_words(corpus: &[(usize, Vec<String>)], length: usize) -> &[String] {
+    get_words(corpus, length).map_or(&[], |words| words.as_slice())
+}
+
+/// IDs of the words of the clue's length that satisfy it.
+fn candidate_set(corpus: &[(usize, Vec<String>)], clue: &WordClue) -> WordSet {
+    let words = length_words(corpus, clue.pattern.len());
+    let matches = matcher(clue);
+    WordSet::from_fn(clue.pattern.len(), words.len(), |id| matches(&words[id]))
+}
+
+fn get_candidates<'a>(corpus: &'a [(usize, Vec<String>)], clue: &WordClue) -> Vec<&'a str> {
+    let words = length_words(corpus, clue.pattern.len());
+    candidate_set(corpus, clue).iter().map(|id| words[id].as_str()).collect()
+}
+
+/// Letters the clue rules out of every position, i.e. not in the word at all.
+fn absent_letters(clue: &WordClue) -> Vec<char> {
+    clue.pattern.iter()
+        .filter_map(|p| match p {
+            WordCluePattern::Exclude(v) => Some(v),
+            _ => None,
+        })
+        .fold(None, |acc: Option<Vec<char>>, v| Some(match acc {
+            None => v.clone(),
+            Some(acc) => acc.into_iter().filter(|c| v.contains(c)).collect(),
+        }))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| !clue.letters.iter().any(|l| l.letter == *c))
+        .collect()
+}
+
+/// Drops the positional part of a clue, keeping only the letter counts and the
+/// letters known to be absent from the word, and applies it to `length` slots.
+fn letters_only(clue: &WordClue, length: usize) -> WordClue {
+    WordClue {
+        pattern: vec![WordCluePattern::Exclude(absent_letters(clue)); length],
+        letters: clue.letters.clone(),
+    }
+}
+
+/// Extends a prefix clue (a token ending in `*`) to `length` slots, or `None`
+/// if the prefix is longer than that.
+fn extend_prefix(clue: &WordClue, length: usize) -> Option<WordClue> {
+    let padding = length.checked_sub(clue.pattern.len())?;
+    Some(WordClue {
+        pattern: clue.pattern.iter().cloned()
+            .chain(repeat_n(WordCluePattern::Exclude(absent_letters(clue)), padding))
+            .collect(),
+        letters: clue.letters.clone(),
+    })
+}
+
+/// What Wordle's hard mode requires of the next guess: green letters kept in
+/// place and yellow letters reused, while gray letters stay allowed.
+fn hard_mode_clue(clue: &WordClue) -> WordClue {
+    WordClue {
+        pattern: clue.pattern.iter().map(|p| match p {
+            WordCluePattern::Letter(l) => WordCluePattern::Letter(*l),
+            WordCluePattern::Exclude(_) => WordCluePattern::Exclude(vec![]),
+        }).collect(),
+        letters: clue.letters.iter().map(|l| WordClueLetter { max: None, ..*l }).collect(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HardMode {
+    #[serde(default)]
+    hard: bool,
+}
 
-    let pattern = Regex::new(
-        &clue.pattern.iter().map(|p| match p {
-            WordCluePattern::Letter(l) => format!("{}", l),
-            WordCluePattern::Exclude(v) => format!("[^{}]", v.iter().collect::<String>()),
-        }).collect::<String>()
-    ).unwrap();
+/// Words of the clue's length that may be guessed next.
+fn allowed_guesses<'a>(corpus: &'a [(usize, Vec<String>)], clue: &WordClue, hard: &HardMode) -> Vec<&'a str> {
+    match hard.hard {
+        true => filter(&hard_mode_clue(clue), length_words(corpus, clue.pattern.len())),
+        false => length_words(corpus, clue.pattern.len()).iter().map(String::as_str).collect(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProbeMode {
+    /// Only suggest throwaway words that cannot be the answer.
+    #[serde(default)]
+    probe: bool,
+}
 
-    let letter_patterns = clue.letters.iter().map(|w| match w {
-        WordClueLetter { letter: l, count: c, exact: true } => {
-            Regex::new(format!(r"([^{}]*{}){{{}}}", l, l, c).as_str()).unwrap()
+/// Drops the guesses that satisfy the clue when only probes are wanted.
+fn probe_guesses<'a>(guesses: Vec<&'a str>, clue: &WordClue, probe: &ProbeMode) -> Vec<&'a str> {
+    match probe.probe {
+        true => {
+            let matches = matcher(clue);
+            guesses.into_iter().filter(|w| !matches(w)).collect()
         },
-        WordClueLetter { letter: l, count: c, exact: false } => {
-            Regex::new(format!(r"([^{}]*{}){{{},}}", l, l, c).as_str()).unwrap()
+        false => guesses,
+    }
+}
+
+#[derive(Serialize)]
+struct LengthGroup {
+    length: usize,
+    words: Vec<String>,
+}
+
+impl Protobuf for Vec<LengthGroup> {}
+
+#[derive(Serialize)]
+struct WordFrequency {
+    word: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency: Option<u64>,
+}
+
+impl Protobuf for Vec<WordFrequency> {
+    fn encode_protobuf(&self) -> Option<Vec<u8>> {
+        Some(proto::WordFrequencyList {
+            words: self.iter().map(|w| proto::WordFrequency { word: w.word.clone(), frequency: w.frequency }).collect(),
+        }.encode_to_vec())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FrequencyQuery {
+    /// Return `{word, frequency}` objects instead of bare words.
+    #[serde(default)]
+    frequency: bool,
+}
+
+/// Usage counts from `FREQUENCY_FILE`, one `WORD COUNT` pair per line.
+fn load_frequencies(alphabet: &Alphabet, path: &str) -> Result<HashMap<String, u64>, String> {
+    corpora::read_file(path).map_err(|e| format!("Failed to read frequency file {}: {}", path, e))?.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let word = alphabet.word(fields.next()?);
+            let count = fields.next()?.parse().map_err(|_| format!("Invalid frequency line: {:?}", line));
+            Some(count.map(|count| (word, count)))
+        })
+        .collect()
+}
+
+/// Sorts candidates with the most used words first, when frequencies are
+/// loaded, and encodes them as requested.
+fn frequency_response(state: &AppState, candidates: Vec<&str>, query: &FrequencyQuery) -> Either<Encoded<Vec<String>>, Encoded<Vec<WordFrequency>>> {
+    let candidates = candidates.into_iter()
+        .map(|w| (w, state.frequencies.get(w).copied()))
+        .sorted_by_key(|(_, f)| Reverse(f.unwrap_or(0)))
+        .collect::<Vec<_>>();
+    match query.frequency {
+        false => Either::Left(Encoded(candidates.into_iter().map(|(w, _)| w.to_string()).collect())),
+        true => Either::Right(Encoded(candidates.into_iter()
+            .map(|(w, frequency)| WordFrequency { word: w.to_string(), frequency })
+            .collect())),
+    }
+}
+
+#[get("/api/words/{pattern:.+}")]
+async fn api_words(path: web::Path<String>, query: web::Query<FrequencyQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    log_slow_query(&state, "words", None, &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, frequency_response(&state, candidates, &query)))
+}
+
+/// A guess and its feedback, in digits or color initials, as a clue token.
+fn guess_token(word: &str, feedback: &str) -> String {
+    format!("{}{}", word, color_digits(feedback).unwrap_or_else(|| feedback.to_string()))
+}
+
+/// Like `/api/words/{pattern}`, with the guesses and their feedback given as
+/// repeated query parameters, paired in order:
+/// `?guess=crane&feedback=01020&guess=slimy&feedback=00100`.
+#[get("/api/words")]
+async fn api_words_query(params: web::Query<Vec<(String, String)>>, query: web::Query<FrequencyQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let values = |key: &'static str| params.iter().filter(move |(k, _)| k == key).map(|(_, v)| v);
+    let pattern = values("guess").zip_longest(values("feedback")).map(|pair| match pair {
+        EitherOrBoth::Both(guess, feedback) => Ok(guess_token(guess, feedback)),
+        EitherOrBoth::Left(guess) => Err(bad_request(format!("Guess {:?} has no feedback", guess))),
+        EitherOrBoth::Right(feedback) => Err(bad_request(format!("Feedback {:?} has no guess", feedback))),
+    }).collect::<Result<Vec<_>>>()?.join("/");
+    let clue = parse_clue(&state.alphabet, &pattern)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    log_slow_query(&state, "words", None, &pattern, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, frequency_response(&state, candidates, &query)))
+}
+
+#[derive(Serialize)]
+struct BatchResult {
+    pattern: String,
+    words: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Protobuf for Vec<BatchResult> {}
+
+/// Filters by many independent clue paths at once, as `/api/words` would.
+/// A path that can't be parsed only fails its own entry.
+#[post("/api/batch")]
+async fn api_batch(patterns: web::Json<Vec<String>>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let results = patterns.iter().map(|pattern| match parse_clue(&state.alphabet, pattern) {
+        Ok(clue) => BatchResult {
+            pattern: pattern.clone(),
+            words: exclude.apply(&state, get_candidates(&state.answers, &clue)).into_iter().map(String::from).collect(),
+            error: None,
         },
+        Err(e) => BatchResult { pattern: pattern.clone(), words: vec![], error: Some(e.to_string()) },
     }).collect::<Vec<_>>();
+    log_slow_query(&state, "batch", None, &patterns.join(" "), results.iter().map(|r| r.words.len()).sum(), start);
+
+    Ok(Encoded(results))
+}
 
-    words.iter().filter(|w| {
-        let w = w.as_ref();
-        pattern.is_match(w) && letter_patterns.iter().all(|p| p.is_match(w))
-    }).map(|w| w.as_ref()).collect()
+/// A letter at a position, counting from 1.
+#[derive(Deserialize)]
+struct PlacedLetter {
+    letter: char,
+    position: usize,
+}
+
+/// How many copies of a letter the word has: at least `min`, which is 1
+/// unless only `max` is given, and at most `max`.
+#[derive(Deserialize)]
+struct RequiredLetter {
+    letter: char,
+    min: Option<usize>,
+    max: Option<usize>,
 }
 
-#[get("/api/words/{pattern:[/a-zA-ZçÇ0-2]+}")]
-async fn api_words(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
-    let clue = {
-        let mut clues : Vec<WordClue> = path.to_uppercase().split('/').map(|token|{
-            extract_answer(token)
-                .map(extract_clue)
-                .map_err( ErrorBadRequest)
-        }).collect::<Result<Vec<_>,_>>()?;
+/// Constraints given directly instead of through guesses and feedback.
+#[derive(Deserialize)]
+struct Constraints {
+    length: usize,
+    #[serde(default)]
+    required: Vec<RequiredLetter>,
+    /// Letters that appear nowhere in the word.
+    #[serde(default)]
+    banned: Vec<char>,
+    #[serde(default)]
+    fixed: Vec<PlacedLetter>,
+    /// Letters that can't be at these positions, wherever else they are.
+    #[serde(default)]
+    banned_positions: Vec<PlacedLetter>,
+}
+
+/// `c` folded, if it is a letter of the alphabet.
+fn constraint_letter(alphabet: &Alphabet, c: char) -> Result<char> {
+    match alphabet.is_letter(c) {
+        true => Ok(alphabet.fold_letter(c)),
+        false => Err(bad_request(format!("{:?} isn't a letter of the alphabet", c))),
+    }
+}
 
-        let mut result = clues.pop().ok_or(ErrorBadRequest("Empty pattern"))?;
-        for clue in clues.into_iter() {
-            result = merge(result, clue).map_err(ErrorBadRequest)?;
+fn constraints_clue(alphabet: &Alphabet, constraints: &Constraints) -> Result<WordClue> {
+    let length = constraints.length;
+    let position = |p: &PlacedLetter| match (1..=length).contains(&p.position) {
+        true => Ok(p.position - 1),
+        false => Err(bad_request(format!("Position {} is outside of a {}-letter word", p.position, length))),
+    };
+    let banned = constraints.banned.iter().map(|c| constraint_letter(alphabet, *c)).collect::<Result<Vec<_>>>()?;
+    let mut pattern = vec![WordCluePattern::Exclude(banned); length];
+    for p in constraints.banned_positions.iter() {
+        let letter = constraint_letter(alphabet, p.letter)?;
+        if let WordCluePattern::Exclude(v) = &mut pattern[position(p)?] {
+            v.push(letter);
         }
-        result
+    }
+    for p in constraints.fixed.iter() {
+        let (i, letter) = (position(p)?, constraint_letter(alphabet, p.letter)?);
+        match pattern[i] {
+            WordCluePattern::Letter(other) if other != letter => return Err(error(
+                ErrorCode::ConflictingClues, format!("Position {} is fixed to both {} and {}", p.position, other, letter)
+            )),
+            _ => pattern[i] = WordCluePattern::Letter(letter),
+        }
+    }
+    let mut letters = Vec::new();
+    for r in constraints.required.iter() {
+        let min = r.min.unwrap_or(if r.max.is_some() { 0 } else { 1 });
+        merge_letter_clue(&mut letters, WordClueLetter { letter: constraint_letter(alphabet, r.letter)?, min, max: r.max });
+    }
+
+    Ok(WordClue {
+        pattern: pattern.into_iter().map(|p| match p {
+            WordCluePattern::Exclude(v) => WordCluePattern::Exclude(v.into_iter().sorted().dedup().collect()),
+            p => p,
+        }).collect(),
+        letters,
+    })
+}
+
+/// Filters by constraints given as JSON rather than as guesses, e.g.
+/// `{"length":5,"required":[{"letter":"e","max":1}],"banned":["s"],
+/// "fixed":[{"letter":"c","position":1}],"banned_positions":[{"letter":"e","position":5}]}`.
+/// Slow queries are logged with the state token of the constraints.
+#[post("/api/constraints")]
+async fn api_constraints(constraints: web::Json<Constraints>, query: web::Query<FrequencyQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    if get_words(&state.corpus, constraints.length).is_none() {
+        return Err(error(ErrorCode::UnknownLength, format!("No words of length {}", constraints.length)));
+    }
+    let clue = constraints_clue(&state.alphabet, &constraints)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    log_slow_query(&state, "constraints", None, &encode_state(&clue), candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, frequency_response(&state, candidates, &query)))
+}
+
+/// Bumped whenever `WordClue` changes shape, so that old state tokens are
+/// refused instead of misread.
+const STATE_VERSION: u8 = 1;
+
+/// A clue as an opaque, URL-safe token: a version byte followed by the clue in
+/// MessagePack, in unpadded base64url.
+fn encode_state(clue: &WordClue) -> String {
+    let body = rmp_serde::to_vec(clue).expect("Failed to encode clue state");
+    URL_SAFE_NO_PAD.encode([STATE_VERSION].into_iter().chain(body).collect::<Vec<_>>())
+}
+
+/// The clue of a state token. Tokens aren't signed, so the clue is checked
+/// like a parsed one would be: a word length of the corpus, letters of the
+/// alphabet and sensible counts.
+fn decode_state(state: &AppState, token: &str) -> Result<WordClue> {
+    let bytes = URL_SAFE_NO_PAD.decode(token).map_err(|_| bad_request("Invalid state token"))?;
+    let clue: WordClue = match bytes.split_first() {
+        Some((&STATE_VERSION, body)) => rmp_serde::from_slice(body).map_err(|_| bad_request("Invalid state token"))?,
+        _ => return Err(bad_request("Unsupported state token version")),
     };
+    let length = clue.pattern.len();
+    let letters = clue.pattern.iter()
+        .flat_map(|p| match p {
+            WordCluePattern::Letter(l) => vec![*l],
+            WordCluePattern::Exclude(v) => v.clone(),
+        })
+        .chain(clue.letters.iter().map(|l| l.letter))
+        .collect::<Vec<_>>();
+    let counts = clue.letters.iter().all(|l| l.min <= length && l.max.is_none_or(|max| l.min <= max));
+    match get_words(&state.corpus, length).is_some() && letters.iter().all(|c| state.alphabet.is_letter(*c)) && counts {
+        true => Ok(clue),
+        false => Err(bad_request("Invalid state token")),
+    }
+}
 
-    Ok(web::Json(
-        get_words(&state.corpus, clue.pattern.len())
-            .map_or(vec![], |words| filter(&clue, words).into_iter().map(String::from).collect::<Vec<_>>())
-    ))
+#[derive(Serialize)]
+struct ClueState {
+    state: String,
+    words: Vec<String>,
 }
 
-fn get_frequency(word: &str) -> Vec<(char, usize)> {
-    word.chars().sorted().group_by(|c| *c).into_iter().map(|(c, g)| (c, g.count())).collect()
+impl Protobuf for ClueState {}
+
+fn state_response(state: &AppState, clue: &WordClue) -> ClueState {
+    ClueState {
+        state: encode_state(clue),
+        words: get_candidates(&state.answers, clue).into_iter().map(String::from).collect(),
+    }
 }
 
-fn score(expected: &[(char, usize)], frequency: &[(char, usize)]) -> usize {
-    let mut score = 0;
-    for (c, f) in expected.iter() {
-        if let Some((_, e)) = frequency.iter().find(|(l, _)| l == c) {
-            score += e.min(f);
-        }
+/// The clue of a pattern as a state token, which `/api/resume` extends with
+/// later guesses without resending the earlier ones.
+#[get("/api/state/{pattern:.+}")]
+async fn api_state(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let clue = parse_clue(&state.alphabet, &path)?;
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(state_response(&state, &clue))))
+}
+
+/// Adds the guesses of a pattern to a state token from `/api/state`.
+#[get("/api/resume/{state}/{pattern:.+}")]
+async fn api_resume(path: web::Path<(String, String)>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let (token, pattern) = path.into_inner();
+    let mut clues = parse_clues(&state.alphabet, &pattern)?;
+    clues.insert(0, decode_state(&state, &token)?);
+    let clue = merge_all(clues)?;
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(state_response(&state, &clue))))
+}
+
+impl Protobuf for GameExport {}
+
+/// The guesses of a pattern as a signed game export, which `/api/import`
+/// resumes on any instance that shares the `EXPORT_KEY`.
+#[get("/api/export/{pattern:.+}")]
+async fn api_export(path: web::Path<String>, export_key: web::Data<ExportKey>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let game = export_key.export(path.into_inner(), encode_state(&clue))?;
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(game)))
+}
+
+/// Checks the signature of a game from `/api/export` and answers like
+/// `/api/state`, so the game can go on with `/api/resume`. The state is still
+/// checked against this instance's corpus, which may not be the exporter's.
+#[post("/api/import")]
+async fn api_import(game: web::Json<GameExport>, export_key: web::Data<ExportKey>, state: web::Data<AppState>) -> Result<impl Responder> {
+    export_key.verify(&game)?;
+    let clue = decode_state(&state, &game.state)?;
+    Ok(Encoded(state_response(&state, &clue)))
+}
+
+/// Parses a plain-text game history of alternating guess and feedback lines.
+fn parse_history(alphabet: &Alphabet, text: &str) -> Result<WordClue> {
+    let lines = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>();
+    if lines.len() % 2 != 0 {
+        return Err(bad_request(format!("Guess {:?} has no feedback line", lines[lines.len() - 1])));
     }
-    score
+    merge_all(
+        lines.chunks(2).map(|pair| parse_token(alphabet, &format!("{} {}", pair[0], pair[1]))).collect::<Result<Vec<_>>>()?
+    )
 }
 
-fn weighted_score(expected: &[(char, usize)], frequency: &[(char, usize)]) -> usize {
-    let mut score = 0;
-    for (c, f) in expected.iter() {
-        if frequency.iter().any(|(l, _)| l == c) {
-            score += f;
-        }
+/// One guess of a JSON game history, with its feedback digits.
+#[derive(Deserialize)]
+struct GuessFeedback {
+    word: String,
+    feedback: String,
+}
+
+#[derive(Deserialize)]
+struct GuessHistory {
+    guesses: Vec<GuessFeedback>,
+}
+
+/// What a tile of a board shows, named as in Wordle itself.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TileState {
+    Correct,
+    Present,
+    Absent,
+    /// Nothing is known about this tile.
+    Unknown,
+}
+
+#[derive(Deserialize)]
+struct BoardCell {
+    letter: char,
+    state: TileState,
+}
+
+/// A JSON game history: either guesses with their feedback, or the board as
+/// rows of cells.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WordsBody {
+    History(GuessHistory),
+    Board(Vec<Vec<BoardCell>>),
+}
+
+/// A board as a clue path, one token per row. Unknown tiles get a wildcard
+/// letter, which makes their score irrelevant.
+fn board_pattern(board: &[Vec<BoardCell>]) -> String {
+    board.iter().map(|row| {
+        let letters = row.iter().map(|cell| match cell.state {
+            TileState::Unknown => '.',
+            _ => cell.letter,
+        });
+        let scores = row.iter().map(|cell| match cell.state {
+            TileState::Correct => '2',
+            TileState::Present => '1',
+            TileState::Absent | TileState::Unknown => '0',
+        });
+        letters.chain(scores).collect::<String>()
+    }).join("/")
+}
+
+/// Filters by a game history, sent either as JSON
+/// (`{"guesses":[{"word":"crane","feedback":"01020"}]}`), as a JSON board
+/// (`[[{"letter":"c","state":"absent"},...]]`) or as plain text with
+/// alternating guess and feedback lines.
+#[post("/api/words")]
+async fn api_words_history(body: Either<web::Json<WordsBody>, String>, query: web::Query<FrequencyQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let (clue, text) = match body {
+        Either::Left(json) => {
+            let pattern = match json.into_inner() {
+                WordsBody::History(history) => history.guesses.iter().map(|g| guess_token(&g.word, &g.feedback)).join("/"),
+                WordsBody::Board(board) => board_pattern(&board),
+            };
+            (parse_clue(&state.alphabet, &pattern)?, pattern)
+        },
+        Either::Right(text) => (parse_history(&state.alphabet, &text)?, text),
+    };
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    log_slow_query(&state, "words", None, &text, candidates.len(), start);
+
+    Ok(frequency_response(&state, candidates, &query))
+}
+
+#[derive(Deserialize)]
+struct SharedGame {
+    share: String,
+    guesses: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SharedRow {
+    guess: String,
+    feedback: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SharedRecord {
+    pattern: String,
+    answer: Option<String>,
+    rows: Vec<SharedRow>,
+    /// Words that satisfy every row without an error.
+    candidates: Vec<String>,
+}
+
+impl Protobuf for SharedRecord {}
+
+/// The problem with one row of a shared game, if any. Fails when the row
+/// cannot be read as a token at all.
+fn check_shared_row(state: &AppState, answer: Option<&str>, clue: &mut Option<WordClue>, guess: &str, feedback: &str) -> Result<Option<String>> {
+    let (letters, tiles) = (guess.chars().count(), feedback.chars().count());
+    if letters != tiles {
+        return Ok(Some(format!("Guess has {} letters but the row has {} tiles", letters, tiles)));
+    }
+    if !is_word(state, guess) {
+        return Ok(Some(format!("{:?} is not in the word list", guess)));
+    }
+    if let Some(answer) = answer {
+        let expected = feedback::to_digits(&feedback::grade(answer, guess));
+        return Ok((expected != feedback)
+            .then(|| format!("Row shows {} but {:?} against {:?} grades as {}", feedback, guess, answer, expected)));
+    }
+
+    let row = parse_token(&state.alphabet, &format!("{}{}", guess, feedback))?;
+    let merged = match clue.take() {
+        Some(previous) => merge(previous, row).ok(),
+        None => Some(row),
+    };
+    match merged {
+        Some(merged) if !candidate_set(&state.answers, &merged).is_empty() => {
+            *clue = Some(merged);
+            Ok(None)
+        },
+        _ => Ok(Some("Row contradicts the previous rows".to_string())),
     }
-    score
 }
 
+/// Aligns the colors of a pasted share grid with the guesses the player says
+/// they made. When the grid is solved every row is re-graded against the
+/// answer; otherwise each row must stay consistent with the ones before it.
+/// The rows are then turned into clues to list the remaining candidates.
+#[post("/api/share")]
+async fn api_share(game: web::Json<SharedGame>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let rows = share::parse_rows(&game.share);
+    let guesses = game.guesses.iter().map(|g| state.alphabet.word(g.trim())).collect::<Vec<_>>();
+    let answer = zip(rows.iter(), guesses.iter())
+        .find(|(row, _)| row.chars().all(|c| c == '2'))
+        .map(|(_, guess)| guess.clone());
 
-#[get("/api/most_letters/{n}/{pattern:[a-zA-ZçÇ]+}")]
-async fn api_most_letters(path: web::Path<(usize, String)>, state: web::Data<AppState>) -> Result<impl Responder> {
-    let (n, pattern) = path.into_inner();
-    let freq = get_frequency(pattern.to_uppercase().as_str());
+    let mut clue = None;
+    let rows = rows.iter().zip_longest(guesses.iter()).map(|pair| match pair {
+        EitherOrBoth::Both(feedback, guess) => Ok(SharedRow {
+            guess: guess.clone(),
+            feedback: feedback.clone(),
+            error: check_shared_row(&state, answer.as_deref(), &mut clue, guess, feedback)?,
+        }),
+        EitherOrBoth::Left(feedback) => Ok(SharedRow {
+            guess: String::new(),
+            feedback: feedback.clone(),
+            error: Some("No guess given for this row".to_string()),
+        }),
+        EitherOrBoth::Right(guess) => Ok(SharedRow {
+            guess: guess.clone(),
+            feedback: String::new(),
+            error: Some("No share row for this guess".to_string()),
+        }),
+    }).collect::<Result<Vec<_>>>()?;
 
-    Ok(web::Json(
-        get_words(&state.corpus, n).map(|ws| 
-            ws.iter().map(|a| (a, score(&freq, &get_frequency(a))))
-            .sorted_by_key(|(_, s)| *s).rev()
-            .group_by(|(_, s)| *s).into_iter()
-            .next()
-            .map_or(vec!["".to_string()], |(_, grp)| grp.into_iter().map(|(w, _)| w.to_owned()).collect())
-        )
-        .unwrap_or(vec!["".to_string()])
-    ))
+    let clues = rows.iter()
+        .filter(|r| r.error.is_none())
+        .map(|r| parse_token(&state.alphabet, &format!("{}{}", r.guess, r.feedback)))
+        .collect::<Result<Vec<_>>>()?;
+    let candidates = match clues.is_empty() {
+        true => vec![],
+        false => get_candidates(&state.answers, &merge_all(clues)?).into_iter().map(str::to_string).collect(),
+    };
+
+    Ok(Encoded(SharedRecord {
+        pattern: rows.iter()
+            .filter(|r| !r.guess.is_empty() && !r.feedback.is_empty())
+            .map(|r| format!("{}{}", r.guess, r.feedback))
+            .join("/"),
+        answer,
+        rows,
+        candidates,
+    }))
 }
 
-#[get("/api/most_common/{n}")]
-async fn api_most_common(path: web::Path<usize>, state: web::Data<AppState>) -> Result<impl Responder> {
-    let n = path.into_inner();
+#[derive(Serialize)]
+struct Validation {
+    consistent: bool,
+    conflicts: Vec<Conflict>,
+}
 
-    Ok(web::Json(
-        zip(get_words(&state.corpus, n), get_words(&state.most_common, n))
-            .map(|(ws, mc)| 
-                ws.iter().map(|a| (a, weighted_score(mc, &get_frequency(a))))
-                .sorted_by_key(|(_, s)| *s).rev()
-                .group_by(|(_, s)| *s).into_iter()
-                .next()
-                .map_or(vec!["".to_string()], |(_, grp)| grp.into_iter().map(|(w, _)| w.to_owned()).collect())
-            )
-            .next().unwrap_or(vec!["".to_string()])
-    ))
+impl Protobuf for Validation {}
+
+/// Checks that the guesses of a pattern can all hold at once, without
+/// filtering the word list, and lists every pair that contradicts.
+#[get("/api/validate/{pattern:.+}")]
+async fn api_validate(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let conflicts = conflicts(&parse_clues(&state.alphabet, &path)?);
+    Ok(cached(&state, RouteClass::Static, Encoded(Validation { consistent: conflicts.is_empty(), conflicts })))
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    let corpus = fs::read_to_string(env::var("CORPUS_FILE").unwrap())
-        .expect("Failed to read corpus.txt").lines()
-        .map(|w| (w.chars().count(), w))
-        .sorted_by_key(|(l,_)| *l)
-        .group_by(|(l,_)| *l).into_iter()
-        .map(|(l, w)| (l, w.into_iter().map(|(_,w)| w).map(String::from).collect_vec()))
-        .collect::<Vec<_>>();
-    let most_common = corpus.iter().map(|(n, words)|{
-        let mut freq = words.iter().map(|w| get_frequency(w)).fold(Vec::new(), |mut acc, f| {
-            for (c, _) in f {
-                match acc.iter().enumerate().find(|(_, (l, _))| l == &c) {
-                    Some((idx, _)) => acc[idx].1 += 1,
-                    None => acc.push((c, 1_usize)),
-                }
+/// Tokens ending in `*` keep their positions as a prefix of longer words; the
+/// rest only contribute their letter counts and absent letters.
+#[get("/api/any_length/{pattern:.+}")]
+async fn api_any_length(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clues = split_tokens(&path).iter().map(|token| match token.strip_suffix('*') {
+        Some(prefix) => parse_token(&state.alphabet, prefix).map(|c| (c, true)),
+        None => parse_token(&state.alphabet, token).map(|c| (c, false)),
+    }).collect::<Result<Vec<_>>>()?;
+
+    let mut groups = Vec::new();
+    for (n, words) in state.answers.iter() {
+        let clues = clues.iter()
+            .map(|(clue, prefix)| if *prefix { extend_prefix(clue, *n) } else { Some(letters_only(clue, *n)) })
+            .collect::<Option<Vec<_>>>();
+        if let Some(clues) = clues {
+            let words = filter(&merge_all(clues)?, words).into_iter().map(String::from).collect::<Vec<_>>();
+            if !words.is_empty() {
+                groups.push(LengthGroup { length: *n, words });
             }
-            acc
-        });
-        freq.sort_by_key(|(_, f)| *f);
-        (*n, freq.into_iter().rev().collect::<Vec<_>>())
-    }).collect::<Vec<_>>();
+        }
+    }
+    log_slow_query(&state, "any_length", None, &path, groups.iter().map(|g| g.words.len()).sum(), start);
 
-    HttpServer::new(move || {
-        App::new()
-            .app_data(web::Data::new(AppState {
-                corpus: corpus.clone(),
-                most_common: most_common.clone(),
-            }))
-            .service(api_words)
-            .service(api_most_letters)
-            .service(api_most_common)
-    })
-    .bind(("0.0.0.0", 8080))?
-    .run()
-    .await
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(groups)))
+}
+
+#[derive(Serialize)]
+struct SoftMatch {
+    word: String,
+    violations: Vec<String>,
+}
+
+impl Protobuf for Vec<SoftMatch> {}
+
+/// Replaces the uncertain tiles of a token with placeholders, except for the
+/// one at `keep`, if any.
+fn certain_part(answer: &WordAnswer, keep: Option<usize>) -> WordAnswer {
+    answer.iter().enumerate().map(|(i, a)| match a.uncertain && Some(i) != keep {
+        true => LetterAnswer { letter: a.letter, answer: LetterAnswerType::Unknown, uncertain: false },
+        false => a.clone(),
+    }).collect()
+}
+
+/// Like `/api/words`, but tiles marked with `~` only rank the candidates
+/// instead of filtering them out: words breaking fewer uncertain tiles come
+/// first, and each one lists the tiles it breaks.
+#[get("/api/soft/{pattern:.+}")]
+async fn api_soft(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let answers = split_tokens(&path).iter()
+        .map(|token| extract_answer(&state.alphabet, token).map_err(|e| ApiError::from(e).into()))
+        .collect::<Result<Vec<_>>>()?;
+    let clue = merge_all(answers.iter().map(|a| extract_clue(certain_part(a, None))).collect())?;
+
+    let mut soft = Vec::new();
+    for answer in answers.iter() {
+        for (i, tile) in answer.iter().enumerate().filter(|(_, a)| a.uncertain) {
+            let check = merge(clue.clone(), extract_clue(certain_part(answer, Some(i))))?;
+            soft.push((format!("{} at position {} in {}", tile.letter, i + 1, canonical_token(answer)), matcher(&check)));
+        }
+    }
+
+    let matches = get_candidates(&state.answers, &clue).into_iter()
+        .map(|w| SoftMatch {
+            word: w.to_string(),
+            violations: soft.iter().filter(|(_, matches)| !matches(w)).map(|(tile, _)| tile.clone()).collect(),
+        })
+        .sorted_by_key(|m| m.violations.len())
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "soft", None, &path, matches.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(matches)))
+}
+
+const MAX_PROBES: usize = 10;
+/// The best probes by letter split that `/api/solve` ranks again by entropy.
+const ENTROPY_POOL: usize = 100;
+
+/// Canonical form of a clue path, as used for opening book keys.
+fn canonical_pattern(alphabet: &Alphabet, pattern: &str) -> Option<String> {
+    split_tokens(pattern).iter()
+        .map(|token| extract_answer(alphabet, token).ok().map(|a| canonical_token(&a)))
+        .collect::<Option<Vec<_>>>()
+        .map(|tokens| tokens.join("/"))
+}
+
+/// Precomputed responses for the first one or two guesses, one entry per line:
+/// a clue path followed by the words to suggest, e.g.
+/// `CRANE00000/SLOTH01000 PIOUS BUMPY`. Blank lines and `#` comments are skipped.
+fn load_opening_book(alphabet: &Alphabet, path: &str) -> Result<HashMap<String, Vec<String>>, String> {
+    corpora::read_file(path).map_err(|e| format!("Failed to read opening book {}: {}", path, e))?.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next().unwrap_or_default();
+            let key = canonical_pattern(alphabet, pattern)
+                .ok_or_else(|| format!("Invalid opening book pattern: {:?}", pattern))?;
+            Ok((key, fields.map(|w| alphabet.word(w)).collect()))
+        })
+        .collect()
+}
+
+fn book_response<'a>(state: &'a AppState, pattern: &str) -> Option<&'a Vec<String>> {
+    canonical_pattern(&state.alphabet, pattern).and_then(|key| state.opening_book.get(&key))
+}
+
+/// Guesses to play first whatever the feedback, the way the user always
+/// opens, read from the query string.
+#[derive(Debug, Default, Deserialize)]
+struct OpeningQuery {
+    opener: Option<String>,
+    /// Only allowed along with an opener.
+    second: Option<String>,
+}
+
+/// `word` folded to the corpus alphabet, if it is a word of `length` letters.
+fn pinned_guess(state: &AppState, word: &str, length: usize) -> Result<String> {
+    let word = state.alphabet.word(word);
+    if word.chars().count() != length {
+        return Err(error(ErrorCode::LengthMismatch, format!("{:?} does not have {} letters", word, length)));
+    }
+    if !is_word(state, &word) {
+        return Err(bad_request(format!("{:?} is not in the word list", word)));
+    }
+    Ok(word)
+}
+
+impl OpeningQuery {
+    /// The pinned guesses in the order they are played, checked against the
+    /// words of `length` letters.
+    fn guesses(&self, state: &AppState, length: usize) -> Result<Vec<String>> {
+        match (&self.opener, &self.second) {
+            (None, Some(_)) => Err(bad_request("second needs an opener")),
+            (opener, second) => opener.iter().chain(second).map(|w| pinned_guess(state, w, length)).collect(),
+        }
+    }
+
+    /// The pinned guess played after the guesses of `pattern`, if those are
+    /// the pinned ones so far.
+    fn next(&self, state: &AppState, pattern: &str, length: usize) -> Result<Option<String>> {
+        let opening = self.guesses(state, length)?;
+        let played = pattern.split('/')
+            .map(|token| extract_answer(&state.alphabet, token).map(|answer| state.alphabet.word(&answer.iter().map(|a| a.letter).collect::<String>())))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ApiError::from)?;
+        Ok(match opening.starts_with(&played) {
+            true => opening.get(played.len()).cloned(),
+            false => None,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Solution {
+    candidates: Vec<String>,
+    probes: Vec<String>,
+}
+
+impl Protobuf for Solution {
+    fn encode_protobuf(&self) -> Option<Vec<u8>> {
+        Some(proto::Solution { candidates: self.candidates.clone(), probes: self.probes.clone() }.encode_to_vec())
+    }
+}
+
+/// Returns the possible answers together with the guesses from the whole
+/// vocabulary whose letters best split them. Once few enough candidates are
+/// left, those guesses are ranked again by the entropy of their feedback, and
+/// then by their worst case, see `strategy::Adaptive`. With `hard=true` only
+/// guesses legal in hard mode are suggested, and with `probe=true` only words
+/// that cannot be the answer. With `opener` and `second`, the second guess
+/// after that opener is always `second`.
+#[get("/api/solve/{pattern:.+}")]
+async fn api_solve(path: web::Path<String>, hard: web::Query<HardMode>, probe: web::Query<ProbeMode>, tiebreak: web::Query<TieBreak>, exclude: web::Query<ExcludeQuery>, opening: web::Query<OpeningQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    let counts = letter_counts(&candidates);
+    let candidate_set = candidates.iter().copied().collect::<HashSet<_>>();
+    let is_answer = |w: &str| candidate_set.contains(w);
+    let kind = StrategyKind::Adaptive.resolve(candidates.len());
+    let pool = match kind {
+        StrategyKind::LetterFrequency => MAX_PROBES,
+        StrategyKind::Minimax => usize::MAX,
+        _ => ENTROPY_POOL,
+    };
+    let pinned = opening.next(&state, &path, clue.pattern.len())?;
+    let probes = match (pinned, book_response(&state, &path)) {
+        (Some(pinned), _) => vec![pinned],
+        (None, Some(book)) => book.iter().filter(|w| exclude.allows(&state, w)).cloned().collect(),
+        (None, None) => {
+            let probes = probe_guesses(allowed_guesses(&state.corpus, &clue, &hard), &clue, &probe).into_iter()
+                .filter(|w| exclude.allows(&state, w))
+                .map(|w| (w, split_score(&counts, candidates.len(), w)))
+                .filter(|(_, s)| *s > 0)
+                .sorted_by(|(a, sa), (b, sb)| sb.cmp(sa).then_with(|| tiebreak.compare(a, b, is_answer, |w| frequency(&state, w))))
+                .take(pool)
+                .map(|(w, _)| w)
+                .collect::<Vec<_>>();
+            kind.strategy().rank(&scoring::to_chars(&candidates), &scoring::to_chars(&probes)).into_iter()
+                .take(MAX_PROBES)
+                .map(|(i, _)| probes[i].to_string())
+                .collect()
+        },
+    };
+    log_slow_query(&state, "solve", None, &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(Solution {
+        candidates: candidates.into_iter().map(String::from).collect(),
+        probes,
+    })))
+}
+
+#[derive(Serialize)]
+struct ParetoGuess {
+    word: String,
+    entropy: f64,
+    probability: f64,
+}
+
+impl Protobuf for Vec<ParetoGuess> {}
+
+/// Guesses for which no other guess is both more informative (entropy of the
+/// feedback over the candidates) and more likely to be the answer.
+#[get("/api/pareto/{pattern:.+}")]
+async fn api_pareto(path: web::Path<String>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    let answers = scoring::to_chars(&candidates);
+    let probability = 1.0 / candidates.len().max(1) as f64;
+    let candidate_set = candidates.iter().copied().collect::<HashSet<_>>();
+
+    let mut front: Vec<ParetoGuess> = Vec::new();
+    let guesses = length_words(&state.corpus, clue.pattern.len()).iter()
+        .filter(|w| exclude.allows(&state, w))
+        .map(|w| ParetoGuess {
+            word: w.to_owned(),
+            entropy: scoring::entropy(&scoring::partition(&w.chars().collect::<Vec<_>>(), &answers)),
+            probability: if candidate_set.contains(w.as_str()) { probability } else { 0.0 },
+        })
+        .sorted_by(|a, b| b.entropy.total_cmp(&a.entropy).then(b.probability.total_cmp(&a.probability)));
+    for guess in guesses {
+        let dominated = front.last().is_some_and(|last| {
+            guess.probability < last.probability || (guess.probability == last.probability && guess.entropy < last.entropy)
+        });
+        if !dominated && !candidates.is_empty() {
+            front.push(guess);
+        }
+    }
+    log_slow_query(&state, "pareto", None, &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(front)))
+}
+
+#[derive(Serialize)]
+struct Suggestion {
+    word: String,
+    entropy: f64,
+    worst_case: usize,
+    expected_remaining: f64,
+    /// Only filled in by `strategy=optimal`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected_guesses: Option<f64>,
+    candidate: bool,
+}
+
+impl Protobuf for Vec<Suggestion> {
+    fn encode_protobuf(&self) -> Option<Vec<u8>> {
+        Some(proto::SuggestionList {
+            suggestions: self.iter().map(|s| proto::Suggestion {
+                word: s.word.clone(),
+                entropy: s.entropy,
+                worst_case: s.worst_case as u64,
+                expected_remaining: s.expected_remaining,
+                expected_guesses: s.expected_guesses,
+                candidate: s.candidate,
+            }).collect(),
+        }.encode_to_vec())
+    }
+}
+
+const DEFAULT_LOOKAHEAD_CANDIDATES: usize = 100;
+const DEFAULT_OPTIMAL_CANDIDATES: usize = 30;
+const DEFAULT_OPTIMAL_DEPTH: usize = 6;
+const DEFAULT_OPTIMAL_TIME_LIMIT_MS: u64 = 5000;
+
+/// `strategy=optimal`: the guesses with the fewest expected guesses when
+/// every later guess is played optimally too, found by an exhaustive search
+/// within `OPTIMAL_MAX_DEPTH` guesses and `OPTIMAL_TIME_LIMIT_MS`.
+fn optimal_suggestions(state: &AppState, guesses: &[Vec<char>], candidates: &[&str]) -> Result<Vec<Suggestion>> {
+    if candidates.len() > state.optimal_candidates {
+        return Err(bad_request(format!(
+            "Too many candidates for the optimal strategy: {} > {}", candidates.len(), state.optimal_candidates
+        )));
+    }
+    let answers = scoring::to_chars(candidates);
+    let candidate_set = candidates.iter().copied().collect::<HashSet<_>>();
+    let limits = optimal::Limits { max_depth: state.optimal_depth, deadline: Instant::now() + state.optimal_time_limit };
+    let best = optimal::best_guesses(guesses, &answers, limits, MAX_PROBES).map_err(|e| bad_request(match e {
+        optimal::SearchError::TimedOut => "Optimal search timed out".to_string(),
+        optimal::SearchError::TooDeep => format!("No strategy finds every answer within {} guesses", state.optimal_depth),
+    }))?;
+    Ok(best.into_iter().map(|(g, expected)| {
+        let score = GuessScore::new(&scoring::partition(&guesses[g], &answers));
+        let word = guesses[g].iter().collect::<String>();
+        Suggestion {
+            candidate: candidate_set.contains(word.as_str()),
+            word,
+            entropy: score.entropy,
+            worst_case: score.worst_case,
+            expected_remaining: score.expected_remaining,
+            expected_guesses: Some(expected),
+        }
+    }).collect())
+}
+/// How many of the best one-ply guesses are searched again at depth 2.
+const LOOKAHEAD_WIDTH: usize = 50;
+
+/// Guesses from the whole vocabulary ranked by the expected information, in
+/// bits, that their feedback reveals about the remaining candidates, or with
+/// `strategy=minimax` by how many candidates they can leave at worst, or with
+/// `strategy=expected_remaining` by how many they leave on average, or with
+/// `strategy=letter_frequency` by how evenly their letters split them, as
+/// `/api/solve` does, or with `strategy=optimal` by the expected number of guesses under perfect play,
+/// for at most `OPTIMAL_MAX_CANDIDATES` candidates. `strategy=adaptive` picks
+/// letter frequency, entropy or minimax by how many candidates are left.
+///
+/// `depth=2` re-ranks the best guesses by what is left after the best
+/// follow-up guess, and is refused above `LOOKAHEAD_MAX_CANDIDATES`
+/// candidates. `hard=true` restricts the guesses to legal hard-mode plays, and
+/// `probe=true` to words that cannot be the answer. Positions listed in
+/// `OPENING_BOOK` get the book's guesses, in its order, whatever the strategy.
+#[get("/api/suggest/{pattern:.+}")]
+async fn api_suggest(path: web::Path<String>, strategy: web::Query<StrategyQuery>, hard: web::Query<HardMode>, probe: web::Query<ProbeMode>, tiebreak: web::Query<TieBreak>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    let answers = scoring::to_chars(&candidates);
+    let candidate_set = candidates.iter().copied().collect::<HashSet<_>>();
+    let is_answer = |w: &str| candidate_set.contains(w);
+
+    let query = strategy.into_inner();
+    if query.depth == 0 || query.depth > 2 {
+        return Err(bad_request(format!("Unsupported depth: {}", query.depth)));
+    }
+    if let Some(book) = book_response(&state, &path) {
+        let suggestions = book.iter()
+            .filter(|w| exclude.allows(&state, w))
+            .map(|w| {
+                let score = GuessScore::new(&scoring::partition(&w.chars().collect::<Vec<_>>(), &answers));
+                Suggestion {
+                    word: w.clone(),
+                    entropy: score.entropy,
+                    worst_case: score.worst_case,
+                    expected_remaining: score.expected_remaining,
+                    expected_guesses: None,
+                    candidate: is_answer(w),
+                }
+            })
+            .collect::<Vec<_>>();
+        log_slow_query(&state, "suggest", Some(query.strategy), &path, candidates.len(), start);
+        return Ok(cached(&state, RouteClass::Dynamic, Encoded(suggestions)));
+    }
+    if query.depth == 2 && candidates.len() > state.lookahead_candidates {
+        return Err(bad_request(format!(
+            "Too many candidates for depth 2: {} > {}", candidates.len(), state.lookahead_candidates
+        )));
+    }
+
+    let guesses = probe_guesses(allowed_guesses(&state.corpus, &clue, &hard), &clue, &probe).into_iter()
+        .filter(|w| exclude.allows(&state, w))
+        .collect::<Vec<_>>();
+    let guesses = scoring::to_chars(&guesses);
+    if let StrategyKind::Optimal = query.strategy {
+        let suggestions = optimal_suggestions(&state, &guesses, &candidates)?;
+        log_slow_query(&state, "suggest", Some(query.strategy), &path, candidates.len(), start);
+        return Ok(cached(&state, RouteClass::Dynamic, Encoded(suggestions)));
+    }
+    let kind = query.strategy.resolve(candidates.len());
+    let ranked = match candidates.is_empty() {
+        true => vec![],
+        false => kind.strategy().rank(&answers, &guesses),
+    };
+    let tiebreak = |a: &str, b: &str| tiebreak.compare(a, b, is_answer, |w| frequency(&state, w));
+    let top = match query.depth {
+        2 => ranked.into_iter()
+            .take(LOOKAHEAD_WIDTH)
+            .map(|(g, _)| (guesses[g].iter().collect::<String>(), strategy::lookahead(kind, &guesses[g], &answers, &guesses)))
+            .sorted_by(|(a, sa), (b, sb)| kind.compare(sa, sb).then_with(|| tiebreak(a, b)))
+            .take(MAX_PROBES)
+            .collect::<Vec<_>>(),
+        _ => ranked.into_iter()
+            .map(|(g, score)| (g, guesses[g].iter().collect::<String>(), score))
+            .sorted_by(|(_, a, sa), (_, b, sb)| sb.total_cmp(sa).then_with(|| tiebreak(a, b)))
+            .take(MAX_PROBES)
+            .map(|(g, w, _)| (w, GuessScore::new(&scoring::partition(&guesses[g], &answers))))
+            .collect(),
+    };
+    let suggestions = top.into_iter()
+        .map(|(w, score)| Suggestion {
+            candidate: is_answer(&w),
+            word: w,
+            entropy: score.entropy,
+            worst_case: score.worst_case,
+            expected_remaining: score.expected_remaining,
+            expected_guesses: None,
+        })
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "suggest", Some(query.strategy), &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(suggestions)))
+}
+
+#[derive(Deserialize)]
+struct MultiBoardRequest {
+    /// One clue path per board, as in `/api/words`.
+    boards: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct MultiBoard {
+    boards: Vec<Vec<String>>,
+    guess: Option<String>,
+}
+
+impl Protobuf for MultiBoard {}
+
+/// Quordle-style games: the candidates of every board, and the guess whose
+/// feedback is expected to reveal the most about all boards together. Ties
+/// go to guesses that may be the answer of some board.
+#[post("/api/multi/words")]
+async fn api_multi_words(request: web::Json<MultiBoardRequest>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clues = request.boards.iter().map(|b| parse_clue(&state.alphabet, b)).collect::<Result<Vec<_>>>()?;
+    let length = clues.first().map_or(0, |c| c.pattern.len());
+    if let Some(clue) = clues.iter().find(|c| c.pattern.len() != length) {
+        return Err(error(ErrorCode::LengthMismatch, format!("Pattern length mismatch: {} != {}", clue.pattern.len(), length)));
+    }
+
+    let boards = clues.iter().map(|c| exclude.apply(&state, get_candidates(&state.answers, c))).collect::<Vec<_>>();
+    let answers = boards.iter().map(|b| scoring::to_chars(b)).collect::<Vec<_>>();
+    let board_sets = boards.iter().map(|b| b.iter().copied().collect::<HashSet<_>>()).collect::<Vec<_>>();
+    let is_answer = |w: &str| board_sets.iter().any(|b| b.contains(w));
+    let guess = length_words(&state.corpus, length).iter()
+        .filter(|_| boards.iter().any(|b| !b.is_empty()))
+        .filter(|w| exclude.allows(&state, w))
+        .map(|w| {
+            let chars = w.chars().collect::<Vec<_>>();
+            (w, answers.iter().map(|a| scoring::entropy(&scoring::partition(&chars, a))).sum::<f64>())
+        })
+        .max_by(|(a, ea), (b, eb)| ea.total_cmp(eb).then(is_answer(a).cmp(&is_answer(b))))
+        .map(|(w, _)| w.to_owned());
+    log_slow_query(&state, "multi", None, &request.boards.join(" "), boards.iter().map(Vec::len).sum(), start);
+
+    Ok(Encoded(MultiBoard {
+        boards: boards.into_iter().map(|b| b.into_iter().map(String::from).collect()).collect(),
+        guess,
+    }))
+}
+
+#[derive(Serialize)]
+struct SimulatedTurn {
+    guess: String,
+    feedback: String,
+    candidates: usize,
+}
+
+#[derive(Serialize)]
+struct Simulation {
+    answer: String,
+    turns: usize,
+    solved: bool,
+    guesses: Vec<SimulatedTurn>,
+}
+
+impl Protobuf for Simulation {
+    fn encode_protobuf(&self) -> Option<Vec<u8>> {
+        Some(proto::Simulation {
+            answer: self.answer.clone(),
+            turns: self.turns as u64,
+            solved: self.solved,
+            guesses: self.guesses.iter().map(|t| proto::SimulatedTurn {
+                guess: t.guess.clone(),
+                feedback: t.feedback.clone(),
+                candidates: t.candidates as u64,
+            }).collect(),
+        }.encode_to_vec())
+    }
+}
+
+const MAX_SIMULATED_TURNS: usize = 20;
+
+/// Plays the suggestion strategy against a known answer until it is found,
+/// starting from `clue` if the game is already under way. The `opening`
+/// guesses, if any, are played first in place of the strategy's; the opener is
+/// the same for every answer of a given length.
+fn simulate(state: &AppState, answer: &str, strategy: StrategyKind, hard: &HardMode, exclude: &ExcludeQuery, mut clue: Option<WordClue>, opening: &[String]) -> Simulation {
+    let mut candidates = exclude.apply(state, match &clue {
+        Some(clue) => get_candidates(&state.answers, clue),
+        None => length_words(&state.answers, answer.chars().count()).iter().map(String::as_str).collect(),
+    });
+    let mut guesses = Vec::new();
+    while guesses.len() < MAX_SIMULATED_TURNS {
+        let allowed = match &clue {
+            Some(clue) => allowed_guesses(&state.corpus, clue, hard),
+            None => length_words(&state.corpus, answer.chars().count()).iter().map(String::as_str).collect::<Vec<_>>(),
+        };
+        let allowed = allowed.into_iter().filter(|w| exclude.allows(state, w)).collect::<Vec<_>>();
+        let guess = match opening.get(guesses.len()) {
+            Some(pinned) => pinned.clone(),
+            None => match strategy::best_guess(strategy, &scoring::to_chars(&allowed), &scoring::to_chars(&candidates)) {
+                Some(guess) => guess.iter().collect(),
+                None => break,
+            },
+        };
+        let feedback = feedback::to_digits(&feedback::grade(answer, &guess));
+        let next = match parse_token(&state.alphabet, &format!("{}{}", guess, feedback)) {
+            Ok(next) => next,
+            Err(_) => break,
+        };
+        let merged = match clue.take() {
+            Some(previous) => merge(previous, next).ok(),
+            None => Some(next),
+        };
+        let Some(merged) = merged else { break };
+        let matches = matcher(&merged);
+        candidates.retain(|w| matches(w));
+        clue = Some(merged);
+        guesses.push(SimulatedTurn { guess: guess.clone(), feedback, candidates: candidates.len() });
+        if guess == answer {
+            break;
+        }
+    }
+
+    Simulation {
+        answer: answer.to_string(),
+        turns: guesses.len(),
+        solved: guesses.last().is_some_and(|t| t.guess == answer),
+        guesses,
+    }
+}
+
+/// Plays `/api/suggest` against `answer`, which must be in the answer list,
+/// and returns every guess with its feedback. Takes the same `strategy` and
+/// `hard` parameters, and `opener` and `second` to play those words first.
+#[get("/api/simulate/{answer:[\\p{L}·]+}")]
+async fn api_simulate(path: web::Path<String>, strategy: web::Query<StrategyQuery>, hard: web::Query<HardMode>, opening: web::Query<OpeningQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let answer = state.alphabet.word(&path);
+    if !is_answer(&state, &answer) {
+        return Err(bad_request(format!("{:?} is not in the answer list", answer)));
+    }
+    let opening = opening.guesses(&state, answer.chars().count())?;
+    let simulation = simulate(&state, &answer, strategy.strategy, &hard, &ExcludeQuery::default(), None, &opening);
+    log_slow_query(&state, "simulate", Some(strategy.strategy), &answer, length_words(&state.corpus, answer.chars().count()).len(), start);
+
+    Ok(cached(&state, RouteClass::Static, Encoded(simulation)))
+}
+
+#[derive(Serialize)]
+struct CandidateCost {
+    word: String,
+    /// Guesses the strategy would still need if this word were the answer.
+    guesses: usize,
+}
+
+impl Protobuf for Vec<CandidateCost> {}
+
+const DEFAULT_COSTS_CANDIDATES: usize = 200;
+
+/// Every remaining candidate annotated with how many more guesses the
+/// suggestion strategy would take to find it, cheapest first. Takes the same
+/// `strategy` and `hard` parameters as `/api/simulate`. Each candidate is a
+/// whole simulated game, so this is refused above `COSTS_MAX_CANDIDATES`
+/// candidates. The exclude filters of `/api/suggest` drop candidates and
+/// guesses here too, in every simulated turn.
+#[get("/api/costs/{pattern:.+}")]
+async fn api_costs(path: web::Path<String>, strategy: web::Query<StrategyQuery>, hard: web::Query<HardMode>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    if candidates.len() > state.costs_candidates {
+        return Err(bad_request(format!("Too many candidates for costs: {} > {}", candidates.len(), state.costs_candidates)));
+    }
+    let allowed = allowed_guesses(&state.corpus, &clue, &hard).into_iter()
+        .filter(|w| exclude.allows(&state, w))
+        .collect::<Vec<_>>();
+    let next = strategy::best_guess(strategy.strategy, &scoring::to_chars(&allowed), &scoring::to_chars(&candidates))
+        .map(|g| g.iter().collect::<String>());
+    let costs = candidates.iter()
+        .map(|w| CandidateCost {
+            word: w.to_string(),
+            guesses: simulate(&state, w, strategy.strategy, &hard, &exclude, Some(clue.clone()), next.as_slice()).turns,
+        })
+        .sorted_by_key(|c| c.guesses)
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "costs", Some(strategy.strategy), &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(costs)))
+}
+
+#[derive(Serialize)]
+struct AdversarialGame {
+    /// Your guesses followed by the minimax solver's, until the answer is
+    /// forced.
+    guesses: Vec<SimulatedTurn>,
+    /// How many guesses, counting yours, the game took against the adversary.
+    guaranteed: usize,
+    solved: bool,
+}
+
+impl Protobuf for AdversarialGame {}
+
+/// Absurdle-style play: the answer is never fixed, and after every guess the
+/// feedback keeping the most candidates alive is chosen. The given guesses
+/// are played first, after which the minimax strategy finishes the game, which
+/// tells how many guesses an opener guarantees at worst.
+#[get("/api/absurdle/{guesses:[/\\p{L}·]+}")]
+async fn api_absurdle(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let openers = state.alphabet.word(&path).split('/').filter(|g| !g.is_empty()).map(String::from).collect::<Vec<_>>();
+    let length = openers.first().map_or(0, |g| g.chars().count());
+    if let Some(bad) = openers.iter().find(|g| g.chars().count() != length || !is_word(&state, g)) {
+        return Err(bad_request(format!("{:?} is not a {}-letter word in the word list", bad, length)));
+    }
+
+    let all = scoring::to_chars(length_words(&state.corpus, length));
+    let mut candidates = scoring::to_chars(length_words(&state.answers, length));
+    let mut guesses = Vec::new();
+    let mut solved = false;
+    while !solved && guesses.len() < MAX_SIMULATED_TURNS {
+        let guess = match openers.get(guesses.len()) {
+            Some(opener) => opener.chars().collect(),
+            None => match strategy::best_guess(StrategyKind::Minimax, &all, &candidates) {
+                Some(guess) => guess.to_vec(),
+                None => break,
+            },
+        };
+        let Some((feedback, left)) = scoring::adversarial(&guess, &candidates) else { break };
+        solved = feedback::solved(&feedback);
+        candidates = left;
+        guesses.push(SimulatedTurn {
+            guess: guess.iter().collect(),
+            feedback: feedback::to_digits(&feedback),
+            candidates: candidates.len(),
+        });
+    }
+    log_slow_query(&state, "absurdle", Some(StrategyKind::Minimax), &path, all.len(), start);
+
+    Ok(cached(&state, RouteClass::Static, Encoded(AdversarialGame {
+        guaranteed: guesses.len(),
+        solved,
+        guesses,
+    })))
+}
+
+#[derive(Serialize)]
+struct Bucket {
+    feedback: String,
+    count: usize,
+    words: Vec<String>,
+}
+
+impl Protobuf for Vec<Bucket> {}
+
+/// The feedback `guess` would get from each remaining candidate, grouped by
+/// feedback, largest groups first.
+#[get("/api/partitions/{guess:[\\p{L}·]+}/{pattern:.+}")]
+async fn api_partitions(path: web::Path<(String, String)>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let (guess, pattern) = path.into_inner();
+    let guess = state.alphabet.word(&guess);
+    let clue = parse_clue(&state.alphabet, &pattern)?;
+    if guess.chars().count() != clue.pattern.len() {
+        return Err(error(ErrorCode::LengthMismatch, format!("Pattern length mismatch: {} != {}", guess.chars().count(), clue.pattern.len())));
+    }
+    let candidates = get_candidates(&state.answers, &clue);
+    let buckets = scoring::split(&guess.chars().collect::<Vec<_>>(), &scoring::to_chars(&candidates)).into_iter()
+        .map(|(feedback, words)| Bucket {
+            feedback: feedback::to_digits(&feedback),
+            count: words.len(),
+            words: words.into_iter().map(|w| w.into_iter().collect()).collect(),
+        })
+        .sorted_by(|a, b| b.count.cmp(&a.count).then_with(|| a.feedback.cmp(&b.feedback)))
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "partitions", None, &pattern, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(buckets)))
+}
+
+const DEFAULT_TREE_MAX_NODES: usize = 20_000;
+/// The subtree cache is dropped once it holds this many entries.
+const MAX_SUBTREES: usize = 100_000;
+
+/// The complete decision tree the suggestion strategy follows after
+/// `opener`: for every feedback, the next guess and its own branches, until
+/// each answer is found. `second`, if given, is played after the opener
+/// whatever its feedback. Trees larger than `TREE_MAX_NODES` nodes are
+/// refused.
+#[get("/api/tree/{opener:[\\p{L}·]+}")]
+async fn api_tree(path: web::Path<String>, strategy: web::Query<StrategyQuery>, opening: web::Query<OpeningQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let opener = state.alphabet.word(&path);
+    if !is_word(&state, &opener) {
+        return Err(bad_request(format!("{:?} is not in the word list", opener)));
+    }
+    let length = opener.chars().count();
+    if opening.opener.as_ref().is_some_and(|w| state.alphabet.word(w) != opener) {
+        return Err(bad_request("The opener is given in the path"));
+    }
+    let opening = OpeningQuery { opener: Some(opener.clone()), second: opening.second.clone() }.guesses(&state, length)?;
+    let opening = opening.iter().map(|w| w.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    let guesses = scoring::to_chars(length_words(&state.corpus, length));
+    let answers = scoring::to_chars(length_words(&state.answers, length));
+
+    {
+        let mut subtrees = state.subtrees.lock().unwrap_or_else(PoisonError::into_inner);
+        if subtrees.len() > MAX_SUBTREES {
+            subtrees.clear();
+        }
+    }
+    let building = state.clone();
+    let kind = strategy.strategy;
+    let candidates = answers.len();
+    let tree = web::block(move || tree::build(kind, &opening, &guesses, &answers, &building.subtrees, building.tree_max_nodes)).await
+        .map_err(|_| bad_request("Failed to build the decision tree"))?
+        .ok_or_else(|| bad_request(format!("Decision tree has more than {} nodes", state.tree_max_nodes)))?;
+    log_slow_query(&state, "tree", Some(kind), &opener, candidates, start);
+
+    Ok(cached(&state, RouteClass::Static, Encoded(tree)))
+}
+
+#[derive(Serialize)]
+struct AntiSuggestion {
+    word: String,
+    expected_hits: f64,
+    worst_hits: usize,
+}
+
+impl Protobuf for Vec<AntiSuggestion> {}
+
+/// Antiwordle, where the goal is to avoid the answer as long as possible:
+/// every guess must follow the clues, so gray letters are banned and the
+/// others must be reused. Ranks those guesses by the expected number of green
+/// and yellow tiles they would reveal, fewest first; guessing the answer
+/// itself counts as all tiles.
+#[get("/api/antiwordle/{pattern:.+}")]
+async fn api_antiwordle(path: web::Path<String>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    let answers = scoring::to_chars(&candidates);
+    let total = answers.len().max(1) as f64;
+
+    let suggestions = filter(&clue, length_words(&state.corpus, clue.pattern.len())).into_iter()
+        .filter(|w| exclude.allows(&state, w))
+        .map(|w| {
+            let guess = w.chars().collect::<Vec<_>>();
+            let hits = answers.iter().map(|a| scoring::hits(&feedback::grade_chars(a, &guess))).collect::<Vec<_>>();
+            AntiSuggestion {
+                word: w.to_string(),
+                expected_hits: hits.iter().sum::<usize>() as f64 / total,
+                worst_hits: hits.into_iter().max().unwrap_or(0),
+            }
+        })
+        .sorted_by(|a, b| a.expected_hits.total_cmp(&b.expected_hits).then(a.worst_hits.cmp(&b.worst_hits)))
+        .take(MAX_PROBES)
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "antiwordle", None, &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(suggestions)))
+}
+
+#[derive(Serialize)]
+struct FibbleMatch {
+    word: String,
+    /// For each row, the position (from 1) of the tile that lied.
+    lies: Vec<usize>,
+}
+
+impl Protobuf for Vec<FibbleMatch> {}
+
+/// A token as a guess and its feedback digits, for variants that compare
+/// feedback directly instead of building a clue.
+fn graded_row(alphabet: &Alphabet, token: &str) -> Result<(Vec<char>, Vec<Tile>)> {
+    extract_answer(alphabet, token).map_err(ApiError::from)?.into_iter()
+        .map(|a| match a.answer {
+            LetterAnswerType::Graded(tile) => Ok((a.letter, tile)),
+            _ => Err(error(ErrorCode::BadToken, format!("Invalid token: {:?}, every tile needs a 0, 1 or 2 score", token))),
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|tiles| tiles.into_iter().unzip())
+}
+
+/// Fibble, where exactly one tile of every row lies: the words for which each
+/// row's feedback is wrong in exactly one tile, with where the lies are.
+#[get("/api/fibble/{pattern:.+}")]
+async fn api_fibble(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let rows = split_tokens(&path).iter().map(|token| graded_row(&state.alphabet, token)).collect::<Result<Vec<_>>>()?;
+    let length = rows.first().map_or(0, |(guess, _)| guess.len());
+    if let Some((guess, _)) = rows.iter().find(|(guess, _)| guess.len() != length) {
+        return Err(error(ErrorCode::LengthMismatch, format!("Pattern length mismatch: {} != {}", guess.len(), length)));
+    }
+
+    let matches = length_words(&state.answers, length).iter()
+        .filter_map(|w| {
+            let answer = w.chars().collect::<Vec<_>>();
+            rows.iter()
+                .map(|(guess, shown)| {
+                    let wrong = zip(feedback::grade_chars(&answer, guess), shown)
+                        .positions(|(actual, shown)| actual != *shown)
+                        .collect::<Vec<_>>();
+                    match wrong[..] {
+                        [lie] => Some(lie + 1),
+                        _ => None,
+                    }
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|lies| FibbleMatch { word: w.to_owned(), lies })
+        })
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "fibble", None, &path, matches.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(matches)))
+}
+
+#[derive(Serialize)]
+struct Explanation {
+    word: String,
+    /// Whether the word is in the answer list at all.
+    answer: bool,
+    survives: bool,
+    violations: Vec<Violation>,
+}
+
+impl Protobuf for Explanation {}
+
+/// Whether `word` satisfies the clues and, if not, every constraint it breaks.
+#[get("/api/explain/{word:[\\p{L}·]+}/{pattern:.+}")]
+async fn api_explain(path: web::Path<(String, String)>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let (word, pattern) = path.into_inner();
+    let word = state.alphabet.word(&word);
+    let clue = parse_clue(&state.alphabet, &pattern)?;
+    let violations = violations(&clue, &word);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(Explanation {
+        answer: is_answer(&state, &word),
+        survives: violations.is_empty(),
+        word,
+        violations,
+    })))
+}
+
+#[derive(Serialize)]
+struct LetterCount {
+    letter: char,
+    /// How many candidates have the letter in this slot.
+    candidates: usize,
+}
+
+#[derive(Serialize)]
+struct PositionLetters {
+    position: usize,
+    letters: Vec<LetterCount>,
+}
+
+impl Protobuf for Vec<PositionLetters> {}
+
+/// For every slot, the letters `candidates` have there and how many have each,
+/// most common first.
+fn slot_letters(length: usize, candidates: &[&str]) -> Vec<Vec<(char, usize)>> {
+    let mut counts: Vec<HashMap<char, usize>> = vec![HashMap::new(); length];
+    for word in candidates.iter() {
+        for (slot, c) in zip(counts.iter_mut(), word.chars()) {
+            *slot.entry(c).or_default() += 1;
+        }
+    }
+    counts.into_iter()
+        .map(|slot| slot.into_iter().sorted_by_key(|(letter, n)| (Reverse(*n), *letter)).collect())
+        .collect()
+}
+
+/// The letters each slot can still hold, taken from the candidates left by the
+/// clues, most common first.
+#[get("/api/positions/{pattern:.+}")]
+async fn api_positions(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = get_candidates(&state.answers, &clue);
+    let positions = slot_letters(clue.pattern.len(), &candidates).into_iter().enumerate()
+        .map(|(i, slot)| PositionLetters {
+            position: i + 1,
+            letters: slot.into_iter().map(|(letter, candidates)| LetterCount { letter, candidates }).collect(),
+        })
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "positions", None, &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(positions)))
+}
+
+const MAX_HINT_LEVEL: usize = 4;
+
+fn default_hint_level() -> usize {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct HintQuery {
+    #[serde(default = "default_hint_level")]
+    level: usize,
+}
+
+#[derive(Serialize)]
+struct PlacedHint {
+    position: usize,
+    letter: char,
+}
+
+/// Every hint up to the requested level; higher levels are left out.
+#[derive(Serialize)]
+struct Hint {
+    level: usize,
+    candidates: usize,
+    /// Level 1: a letter the clues don't reveal yet, the one most candidates
+    /// have.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    letter: Option<char>,
+    /// Level 2: the open slot most candidates agree on, with their letter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<PlacedHint>,
+    /// Level 3: at most three candidates, most used first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    words: Vec<String>,
+    /// Level 4: the guess `/api/suggest` would play.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    guess: Option<String>,
+}
+
+impl Protobuf for Hint {}
+
+/// Hints of increasing strength about the candidates left by the clues, from
+/// `level=1` (a letter) up to `level=4` (the next guess). The same clues always
+/// get the same hints: ties go to the first letter, slot or corpus word.
+#[get("/api/hint/{pattern:.+}")]
+async fn api_hint(path: web::Path<String>, query: web::Query<HintQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    if !(1..=MAX_HINT_LEVEL).contains(&query.level) {
+        return Err(bad_request(format!("Hint level must be between 1 and {}", MAX_HINT_LEVEL)));
+    }
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = exclude.apply(&state, get_candidates(&state.answers, &clue));
+    let level = query.level;
+
+    let letter = candidates.iter()
+        .flat_map(|w| w.chars().unique())
+        .filter(|c| !clue.letters.iter().any(|l| l.letter == *c))
+        .counts()
+        .into_iter()
+        .sorted_by_key(|(c, n)| (Reverse(*n), *c))
+        .map(|(c, _)| c)
+        .next();
+    let position = (level >= 2).then(|| {
+        zip(&clue.pattern, slot_letters(clue.pattern.len(), &candidates)).enumerate()
+            .filter(|(_, (p, _))| !matches!(p, WordCluePattern::Letter(_)))
+            .filter_map(|(i, (_, slot))| slot.first().map(|(letter, n)| (i, *letter, *n)))
+            .max_by_key(|(i, _, n)| (*n, Reverse(*i)))
+            .map(|(i, letter, _)| PlacedHint { position: i + 1, letter })
+    }).flatten();
+    let words = match level >= 3 {
+        true => candidates.iter()
+            .sorted_by_key(|w| Reverse(state.frequencies.get(**w).copied().unwrap_or(0)))
+            .take(3)
+            .map(|w| w.to_string())
+            .collect(),
+        false => vec![],
+    };
+    let guess = (level >= 4).then(|| {
+        let allowed = allowed_guesses(&state.corpus, &clue, &HardMode::default()).into_iter()
+            .filter(|w| exclude.allows(&state, w))
+            .collect::<Vec<_>>();
+        let allowed = scoring::to_chars(&allowed);
+        strategy::best_guess(StrategyKind::default(), &allowed, &scoring::to_chars(&candidates))
+            .map(|g| g.iter().collect::<String>())
+    }).flatten();
+    log_slow_query(&state, "hint", None, &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(Hint { level, candidates: candidates.len(), letter, position, words, guess })))
+}
+
+#[derive(Serialize)]
+struct TurnProgress {
+    guess: String,
+    candidates: usize,
+    bits: f64,
+    letters_resolved: usize,
+}
+
+impl Protobuf for Vec<TurnProgress> {}
+
+/// Replays the game one guess at a time, reporting how much each guess
+/// narrowed the candidates down.
+#[get("/api/progress/{pattern:.+}")]
+async fn api_progress(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let mut clue: Option<WordClue> = None;
+    let mut candidates: Option<WordSet> = None;
+    let mut turns = Vec::new();
+    for (token, next) in zip(split_tokens(&path), parse_clues(&state.alphabet, &path)?) {
+        let next = match clue.take() {
+            Some(previous) => merge(previous, next)?,
+            None => next,
+        };
+        let words = length_words(&state.answers, next.pattern.len());
+        let before = candidates.as_ref().map_or(words.len(), WordSet::len);
+        let narrowed = match candidates.take() {
+            Some(mut set) => {
+                let matches = matcher(&next);
+                set.retain(|id| matches(&words[id]));
+                set
+            },
+            None => candidate_set(&state.answers, &next),
+        };
+        let after = narrowed.len();
+        turns.push(TurnProgress {
+            guess: token.to_string(),
+            candidates: after,
+            bits: (before.max(1) as f64 / after.max(1) as f64).log2(),
+            letters_resolved: next.pattern.iter().filter(|p| matches!(p, WordCluePattern::Letter(_))).count(),
+        });
+        candidates = Some(narrowed);
+        clue = Some(next);
+    }
+    log_slow_query(&state, "progress", None, &path, candidates.map_or(0, |c| c.len()), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(turns)))
+}
+
+#[get("/api/clusters/{pattern:.+}")]
+async fn api_clusters(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let clue = parse_clue(&state.alphabet, &path)?;
+    let candidates = get_candidates(&state.answers, &clue);
+    let clusters = cluster::cluster(&candidates);
+    log_slow_query(&state, "clusters", None, &path, candidates.len(), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(clusters)))
+}
+
+fn score(expected: &[(char, usize)], frequency: &[(char, usize)]) -> usize {
+    let mut score = 0;
+    for (c, f) in expected.iter() {
+        if let Some((_, e)) = frequency.iter().find(|(l, _)| l == c) {
+            score += e.min(f);
+        }
+    }
+    score
+}
+
+/// Number of words with each letter in each position.
+fn positional_counts(length: usize, words: &[String]) -> Vec<HashMap<char, usize>> {
+    let mut counts = vec![HashMap::new(); length];
+    for w in words {
+        for (i, c) in w.chars().enumerate() {
+            *counts[i].entry(c).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Sums how common each letter of `word` is in its position, counting a
+/// repeated letter only where it first appears.
+fn positional_score(counts: &[HashMap<char, usize>], word: &str) -> usize {
+    let chars = word.chars().collect::<Vec<_>>();
+    chars.iter().enumerate()
+        .filter(|(i, c)| !chars[..*i].contains(c))
+        .map(|(i, c)| counts[i].get(c).copied().unwrap_or(0))
+        .sum()
+}
+
+fn weighted_score(expected: &[(char, usize)], frequency: &[(char, usize)]) -> usize {
+    let mut score = 0;
+    for (c, f) in expected.iter() {
+        if frequency.iter().any(|(l, _)| l == c) {
+            score += f;
+        }
+    }
+    score
+}
+
+
+#[derive(Serialize)]
+struct ScoredWord {
+    word: String,
+    score: usize,
+}
+
+impl Protobuf for Vec<ScoredWord> {}
+
+#[derive(Debug, Default, Deserialize)]
+struct ScoresQuery {
+    /// Return the best `MAX_SCORED` words with their scores instead of only
+    /// the words sharing the top score.
+    #[serde(default)]
+    scores: bool,
+}
+
+const MAX_SCORED: usize = 100;
+
+fn ranked_response(state: &AppState, scored: Vec<(&String, usize)>, query: &ScoresQuery, tiebreak: &TieBreak) -> Either<Encoded<Vec<String>>, Encoded<Vec<ScoredWord>>> {
+    let ranked = scored.into_iter()
+        .sorted_by(|(a, sa), (b, sb)| sb.cmp(sa).then_with(|| {
+            tiebreak.compare(a, b, |w| is_answer(state, w), |w| frequency(state, w))
+        }));
+    match query.scores {
+        true => Either::Right(Encoded(ranked
+            .take(MAX_SCORED)
+            .map(|(w, score)| ScoredWord { word: w.to_owned(), score })
+            .collect())),
+        false => Either::Left(Encoded(ranked
+            .group_by(|(_, s)| *s).into_iter()
+            .next()
+            .map_or(vec!["".to_string()], |(_, grp)| grp.into_iter().map(|(w, _)| w.to_owned()).collect()))),
+    }
+}
+
+#[get("/api/most_letters/{n}/{pattern:[\\p{L}·]+}")]
+async fn api_most_letters(path: web::Path<(usize, String)>, query: web::Query<ScoresQuery>, tiebreak: web::Query<TieBreak>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let (n, pattern) = path.into_inner();
+    let freq = get_frequency(&state.alphabet.word(&pattern));
+
+    let scored = get_words(&state.corpus, n).map_or(vec![], |ws|
+        ws.iter().filter(|w| exclude.allows(&state, w)).map(|a| (a, score(&freq, &get_frequency(a)))).collect()
+    );
+    log_slow_query(&state, "most_letters", None, &pattern, get_words(&state.corpus, n).map_or(0, Vec::len), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, ranked_response(&state, scored, &query, &tiebreak)))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MostCommonQuery {
+    /// Score letters by how common they are in their position instead of
+    /// anywhere in the word.
+    #[serde(default)]
+    positional: bool,
+}
+
+#[get("/api/most_common/{n}")]
+async fn api_most_common(path: web::Path<usize>, query: web::Query<MostCommonQuery>, scores: web::Query<ScoresQuery>, tiebreak: web::Query<TieBreak>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let n = path.into_inner();
+
+    let scored = zip(get_words(&state.corpus, n), zip(state.most_common(n), state.positional(n)))
+        .map(|(ws, (mc, pc))|
+            ws.iter().filter(|w| exclude.allows(&state, w)).map(|a| (a, match query.positional {
+                true => positional_score(pc, a),
+                false => weighted_score(mc, &get_frequency(a)),
+            })).collect()
+        )
+        .next().unwrap_or_default();
+    log_slow_query(&state, "most_common", None, &n.to_string(), get_words(&state.corpus, n).map_or(0, Vec::len), start);
+
+    Ok(cached(&state, RouteClass::Static, ranked_response(&state, scored, &scores, &tiebreak)))
+}
+
+/// Probes that try as many letters absent from every previous guess as
+/// possible, ignoring what the feedback of those guesses was.
+#[get("/api/fresh/{n}/{guesses:.+}")]
+async fn api_fresh(path: web::Path<(usize, String)>, tiebreak: web::Query<TieBreak>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let (n, guesses) = path.into_inner();
+    let tried = state.alphabet.word(&guesses).chars().filter(|c| *c != '/').collect::<Vec<_>>();
+
+    let words = zip(get_words(&state.corpus, n), state.most_common(n))
+        .map(|(ws, mc)| ws.iter()
+            .filter(|w| exclude.allows(&state, w))
+            .map(|w| {
+                let fresh = get_frequency(w).into_iter().filter(|(c, _)| !tried.contains(c)).collect::<Vec<_>>();
+                (w, (fresh.len(), weighted_score(mc, &fresh)))
+            })
+            .filter(|(_, (fresh, _))| *fresh > 0)
+            .sorted_by(|(a, sa), (b, sb)| sb.cmp(sa).then_with(|| {
+                tiebreak.compare(a, b, |w| is_answer(&state, w), |w| frequency(&state, w))
+            }))
+            .take(MAX_PROBES)
+            .map(|(w, _)| w.to_owned())
+            .collect::<Vec<_>>()
+        )
+        .next().unwrap_or_default();
+    log_slow_query(&state, "fresh", None, &guesses, get_words(&state.corpus, n).map_or(0, Vec::len), start);
+
+    Ok(cached(&state, RouteClass::Dynamic, Encoded(words)))
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OpenerMetric {
+    #[default]
+    Coverage,
+    Entropy,
+}
+
+fn default_opener_size() -> usize {
+    2
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenerQuery {
+    #[serde(default = "default_opener_size")]
+    words: usize,
+    #[serde(default)]
+    metric: OpenerMetric,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Opener {
+    words: Vec<String>,
+    letters: usize,
+    coverage: usize,
+    entropy: f64,
+}
+
+impl Protobuf for Vec<Opener> {}
+
+/// Searched openers by length, size, metric and whether they are safe.
+type OpenerCache = Mutex<HashMap<(usize, usize, OpenerMetric, bool), Vec<Opener>>>;
+
+const MAX_OPENERS: usize = 5;
+/// How many of the best covering sets are compared by entropy.
+const OPENER_SHORTLIST: usize = 50;
+
+/// Fixed sequences of two or three openers of length `n`. By default they are
+/// ranked by coverage, the sum over their distinct letters of how many words
+/// contain each one; `metric=entropy` re-ranks the best covering sets by the
+/// information their combined feedback reveals. Results are cached.
+#[get("/api/openers/{n}")]
+async fn api_openers(path: web::Path<usize>, query: web::Query<OpenerQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let n = path.into_inner();
+    if !(2..=3).contains(&query.words) {
+        return Err(bad_request(format!("Openers must have 2 or 3 words, not {}", query.words)));
+    }
+    let key = (n, query.words, query.metric, exclude.safe());
+    if let Some(openers) = state.openers.lock().unwrap_or_else(PoisonError::into_inner).get(&key) {
+        return Ok(cached(&state, RouteClass::Static, Encoded(openers.clone())));
+    }
+
+    let words = length_words(&state.corpus, n).iter().filter(|w| exclude.allows(&state, w)).cloned().collect::<Vec<_>>();
+    let weights = state.most_common(n).map_or(&[][..], Vec::as_slice);
+    let guesses = scoring::to_chars(&words);
+    let answers = scoring::to_chars(length_words(&state.answers, n));
+    let shortlist = match query.metric {
+        OpenerMetric::Coverage => MAX_OPENERS,
+        OpenerMetric::Entropy => OPENER_SHORTLIST,
+    };
+    let openers = openers::best_openers(&words, weights, query.words, shortlist).into_iter()
+        .map(|(coverage, ids)| {
+            let guesses = ids.iter().map(|id| guesses[*id].clone()).collect::<Vec<_>>();
+            Opener {
+                words: ids.iter().map(|id| words[*id].clone()).collect(),
+                letters: guesses.concat().into_iter().unique().count(),
+                coverage,
+                entropy: scoring::entropy(&scoring::partition_many(&guesses, &answers)),
+            }
+        })
+        .sorted_by(|a, b| match query.metric {
+            OpenerMetric::Coverage => b.coverage.cmp(&a.coverage),
+            OpenerMetric::Entropy => b.entropy.total_cmp(&a.entropy),
+        })
+        .take(MAX_OPENERS)
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "openers", None, &n.to_string(), words.len(), start);
+
+    state.openers.lock().unwrap_or_else(PoisonError::into_inner).insert(key, openers.clone());
+    Ok(cached(&state, RouteClass::Static, Encoded(openers)))
+}
+
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Serialize)]
+struct WordValidity {
+    word: String,
+    guess: bool,
+    answer: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suggestions: Vec<String>,
+}
+
+impl Protobuf for WordValidity {}
+impl Protobuf for Vec<WordValidity> {}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let sub = diag + usize::from(ca != cb);
+            diag = row[j + 1];
+            row[j + 1] = sub.min(row[j] + 1).min(diag + 1);
+        }
+    }
+    row[b.len()]
+}
+
+fn closest_words(corpus: &[(usize, Vec<String>)], word: &str) -> Vec<String> {
+    let word = word.chars().collect::<Vec<_>>();
+    corpus.iter()
+        .filter(|(l, _)| l.abs_diff(word.len()) <= MAX_SUGGESTION_DISTANCE)
+        .flat_map(|(_, ws)| ws.iter())
+        .map(|w| (w, edit_distance(&word, &w.chars().collect::<Vec<_>>())))
+        .filter(|(_, d)| *d <= MAX_SUGGESTION_DISTANCE)
+        .sorted_by_key(|(_, d)| *d)
+        .take(MAX_SUGGESTIONS)
+        .map(|(w, _)| w.to_owned())
+        .collect()
+}
+
+fn word_id(state: &AppState, word: &str) -> Option<usize> {
+    state.word_ids(word.chars().count()).and_then(|ids| ids.get(word).copied())
+}
+
+fn is_word(state: &AppState, word: &str) -> bool {
+    word_id(state, word).is_some()
+}
+
+fn frequency(state: &AppState, word: &str) -> u64 {
+    state.frequencies.get(word).copied().unwrap_or(0)
+}
+
+fn is_answer(state: &AppState, word: &str) -> bool {
+    state.answer_set(word.chars().count()).is_some_and(|ws| ws.contains(word))
+}
+
+fn get_validity(state: &AppState, word: &str) -> WordValidity {
+    let word = state.alphabet.word(word);
+    let valid = is_word(state, &word);
+    let answer = is_answer(state, &word);
+    let suggestions = if valid { vec![] } else { closest_words(&state.corpus, &word) };
+    WordValidity { word, guess: valid, answer, suggestions }
+}
+
+#[get("/api/valid/{word:[\\p{L}·]+}")]
+async fn api_valid(path: web::Path<String>, state: web::Data<AppState>) -> Result<impl Responder> {
+    Ok(cached(&state, RouteClass::Static, Encoded(get_validity(&state, &path))))
+}
+
+const DEFAULT_VALID_MAX_WORDS: usize = 1_000;
+
+/// The validity of every word of the list, which may hold up to
+/// `VALID_MAX_WORDS` words.
+#[post("/api/valid")]
+async fn api_valid_bulk(words: web::Json<Vec<String>>, state: web::Data<AppState>) -> Result<impl Responder> {
+    if words.len() > state.valid_max_words {
+        return Err(bad_request(format!("The list has {} words, at most {} are allowed", words.len(), state.valid_max_words)));
+    }
+    Ok(Encoded(
+        words.iter().map(|w| get_validity(&state, w)).collect::<Vec<_>>()
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditQuery {
+    /// How many of the latest entries to return, 100 by default.
+    limit: Option<usize>,
+}
+
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+
+/// The latest changes made through admin routes, oldest first, with the
+/// admin token each was made with and when.
+#[get("/admin/audit")]
+async fn admin_audit(req: HttpRequest, query: web::Query<AuditQuery>, admin_token: web::Data<AdminToken>, audit: web::Data<AuditLog>) -> Result<impl Responder> {
+    admin_token.authorize(&req)?;
+    Ok(Encoded(audit.entries(query.limit.unwrap_or(DEFAULT_AUDIT_LIMIT))))
+}
+
+#[get("/api/ladder/{from:[\\p{L}·]+}/{to:[\\p{L}·]+}")]
+async fn api_ladder(path: web::Path<(String, String)>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let (from, to) = path.into_inner();
+    let (from, to) = (state.alphabet.word(&from), state.alphabet.word(&to));
+    let n = from.chars().count();
+    if n != to.chars().count() {
+        return Err(error(ErrorCode::LengthMismatch, format!("Length mismatch: {} != {}", n, to.chars().count())));
+    }
+
+    let words = get_words(&state.corpus, n).ok_or(error(ErrorCode::UnknownLength, format!("No words of length {}", n)))?;
+    let find = |word: &str| word_id(&state, word)
+        .ok_or(bad_request(format!("Unknown word: {:?}", word)));
+    let (from, to) = (find(&from)?, find(&to)?);
+
+    Ok(cached(&state, RouteClass::Static, Encoded(
+        state.neighbors(n)
+            .and_then(|neighbors| ladder::shortest_ladder(neighbors, from, to))
+            .map_or(vec![], |path| path.into_iter().map(|i| words[i].to_owned()).collect::<Vec<_>>())
+    )))
+}
+
+#[derive(Serialize)]
+struct Grade {
+    answer: String,
+    guess: String,
+    feedback: String,
+}
+
+impl Protobuf for Grade {}
+
+/// Crossword-style search: corpus words with the given letters in place and
+/// anything at the wildcards, `_`, `.` or `?` (which has to be sent as `%3F`),
+/// as in `c_a_e`.
+#[get("/api/match/{pattern}")]
+async fn api_match(path: web::Path<String>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let pattern = state.alphabet.word(&path).chars()
+        .map(|c| match c {
+            '_' | '.' | '?' => Ok(WordCluePattern::Exclude(vec![])),
+            c if state.alphabet.is_letter(c) => Ok(WordCluePattern::Letter(c)),
+            c => Err(bad_request(format!("Invalid character {:?} in {:?}, use letters and wildcards (_ . ?)", c, path.as_str()))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let clue = WordClue { pattern, letters: vec![] };
+    let words = exclude.apply(&state, filter(&clue, length_words(&state.corpus, clue.pattern.len())))
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    log_slow_query(&state, "match", None, &path, words.len(), start);
+
+    Ok(cached(&state, RouteClass::Static, Encoded(words)))
+}
+
+#[derive(Debug, Deserialize)]
+struct RegexQuery {
+    q: String,
+}
+
+const MAX_REGEX_LENGTH: usize = 256;
+/// Bytes the compiled regex may take, so that patterns like `(a{1000}){1000}`
+/// are refused.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// The words of length `n` matched in full by the regex `q`, ignoring case.
+/// Matching takes linear time whatever the regex, so only its length and
+/// compiled size are limited.
+#[get("/api/regex/{n}")]
+async fn api_regex(path: web::Path<usize>, query: web::Query<RegexQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let n = path.into_inner();
+    if query.q.len() > MAX_REGEX_LENGTH {
+        return Err(bad_request(format!("The regex is {} bytes long, at most {} are allowed", query.q.len(), MAX_REGEX_LENGTH)));
+    }
+    let regex = RegexBuilder::new(&format!("^(?:{})$", query.q))
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .dfa_size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| bad_request(format!("Invalid regex: {}", e)))?;
+    let matches = get_words(&state.corpus, n).ok_or(error(ErrorCode::UnknownLength, format!("No words of length {}", n)))?
+        .iter()
+        .filter(|w| regex.is_match(w))
+        .map(String::as_str)
+        .collect();
+    let words = exclude.apply(&state, matches).into_iter().map(String::from).collect::<Vec<_>>();
+    log_slow_query(&state, "regex", None, &query.q, words.len(), start);
+
+    Ok(cached(&state, RouteClass::Static, Encoded(words)))
+}
+
+#[derive(Debug, Deserialize)]
+struct AnagramQuery {
+    /// Also list the shorter words spelled with some of the letters.
+    #[serde(default)]
+    partial: bool,
+}
+
+/// Corpus words spelled with exactly the given letters, or with some of them
+/// when `partial=true`, longest first. Repeated letters can only be used as
+/// many times as they are given.
+#[get("/api/anagrams/{letters:[\\p{L}·]+}")]
+async fn api_anagrams(path: web::Path<String>, query: web::Query<AnagramQuery>, exclude: web::Query<ExcludeQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let start = Instant::now();
+    let letters = state.alphabet.word(&path);
+    let available = get_frequency(&letters);
+    let n = letters.chars().count();
+    let fits = |word: &str| get_frequency(word).iter()
+        .all(|(c, k)| available.iter().any(|(a, m)| a == c && m >= k));
+    let fitting = state.corpus.iter().rev()
+        .filter(|(length, _)| *length == n || (query.partial && *length < n))
+        .flat_map(|(_, words)| words.iter().filter(|w| fits(w)).map(String::as_str))
+        .collect();
+    let words = exclude.apply(&state, fitting).into_iter().map(String::from).collect::<Vec<_>>();
+    log_slow_query(&state, "anagrams", None, &path, words.len(), start);
+
+    Ok(cached(&state, RouteClass::Static, Encoded(words)))
+}
+
+/// The languages that can be put after `/api/` to use their corpus instead of
+/// the default one, as in `/api/ca/words/{pattern}` or
+/// `/api/words/{pattern}?corpus=ca`.
+#[get("/api/languages")]
+async fn api_languages(corpora: web::Data<Corpora>, state: web::Data<AppState>) -> Result<impl Responder> {
+    Ok(cached(&state, RouteClass::Static, Encoded(corpora.languages())))
+}
+
+#[derive(Serialize)]
+struct LetterStat {
+    letter: char,
+    /// How many answers have the letter.
+    answers: usize,
+}
+
+#[derive(Serialize)]
+struct BigramStat {
+    bigram: String,
+    /// How many times answers spell it.
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct LengthStats {
+    length: usize,
+    words: usize,
+    answers: usize,
+    letters: Vec<LetterStat>,
+    /// The most common pairs of adjacent letters, most common first.
+    bigrams: Vec<BigramStat>,
+    /// How many answers have no vowel, one vowel, two vowels and so on.
+    vowels_per_word: Vec<usize>,
+    vowels: usize,
+    consonants: usize,
+}
+
+impl Protobuf for Vec<LengthStats> {}
+
+const MAX_BIGRAMS: usize = 10;
+
+/// Statistics of every word length of the corpus, to check that a corpus was
+/// loaded as expected. Letters, bigrams and vowels are counted over the
+/// answers.
+#[get("/api/corpus/stats")]
+async fn api_corpus_stats(state: web::Data<AppState>) -> Result<impl Responder> {
+    let stats = state.corpus.iter()
+        .map(|(n, words)| {
+            let answers = length_words(&state.answers, *n);
+            let bigrams = answers.iter()
+                .flat_map(|w| w.chars().tuple_windows::<(char, char)>())
+                .counts()
+                .into_iter()
+                .sorted_by_key(|((a, b), count)| (Reverse(*count), *a, *b))
+                .take(MAX_BIGRAMS)
+                .map(|((a, b), count)| BigramStat { bigram: [a, b].iter().collect(), count })
+                .collect();
+            let mut vowels_per_word = vec![0; n + 1];
+            for word in answers {
+                vowels_per_word[word.chars().filter(|c| state.alphabet.is_vowel(*c)).count()] += 1;
+            }
+            let vowels = vowels_per_word.iter().enumerate().map(|(k, words)| k * words).sum();
+            LengthStats {
+                length: *n,
+                words: words.len(),
+                answers: answers.len(),
+                letters: state.most_common(*n).map_or(vec![], |counts| counts.iter()
+                    .map(|(letter, answers)| LetterStat { letter: *letter, answers: *answers })
+                    .collect()),
+                bigrams,
+                vowels_per_word,
+                vowels,
+                consonants: answers.iter().map(|w| w.chars().count()).sum::<usize>() - vowels,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(cached(&state, RouteClass::Static, Encoded(stats)))
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadQuery {
+    /// Lowercase letters, digits and dashes; the corpus is then queried under
+    /// `/api/custom-{name}/...`.
+    name: String,
+    /// Seconds before the corpus is dropped, `UPLOAD_TTL_SECONDS` by default.
+    ttl: Option<u64>,
+    /// Ignore accents, in the words and in the clues.
+    #[serde(default)]
+    fold_diacritics: bool,
+    /// The language whose alphabet and word frequencies the corpus takes,
+    /// instead of the one its letters look like.
+    language: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RejectedWord {
+    line: usize,
+    word: String,
+}
+
+#[derive(Serialize)]
+struct UploadedCorpus {
+    id: String,
+    words: usize,
+    /// The language given or detected, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in: Option<u64>,
+}
+
+impl Protobuf for UploadedCorpus {}
+
+const MAX_UPLOAD_NAME: usize = 32;
+/// Rejected words listed in the error of an invalid upload.
+const MAX_REJECTED_WORDS: usize = 20;
+
+/// Creates an in-memory corpus from a plain-text word list, one word per line,
+/// that can then be queried like a language. Fails if any line holds something
+/// other than letters. The corpus takes the alphabet and word frequencies of
+/// `language`, or else of the language of `LANGUAGES_DIR` whose corpus uses
+/// letters in the closest proportions, among those that spell every word.
+#[post("/api/corpora")]
+async fn api_upload_corpus(req: HttpRequest, payload: web::Payload, query: web::Query<UploadQuery>, corpora: web::Data<Corpora>, admin_token: web::Data<AdminToken>, audit: web::Data<AuditLog>) -> Result<impl Responder> {
+    let name = &query.name;
+    if name.is_empty() || name.len() > MAX_UPLOAD_NAME || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        return Err(bad_request(format!("Invalid corpus name: {:?}, use up to {} lowercase letters, digits and dashes", name, MAX_UPLOAD_NAME)));
+    }
+    if query.ttl == Some(0) {
+        return Err(bad_request("The time to live must be at least one second"));
+    }
+    let max_bytes = corpora.upload_limits.max_bytes();
+    let body = payload.to_bytes_limited(max_bytes).await
+        .map_err(|_| bad_request(format!("The word list is over {} bytes", max_bytes)))??;
+    let body = std::str::from_utf8(&body).map_err(|_| bad_request("The word list isn't valid UTF-8"))?;
+    let lines = body.lines().enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return Err(bad_request("The word list is empty"));
+    }
+    if lines.len() > corpora.upload_limits.max_words {
+        return Err(bad_request(format!("The word list has {} words, at most {} are allowed", lines.len(), corpora.upload_limits.max_words)));
+    }
+    let base = Alphabet::new(corpora.settings.case).folding_diacritics(query.fold_diacritics);
+    let rejected = lines.iter()
+        .filter(|(_, word)| !base.word(word).chars().all(|c| base.is_letter(c)))
+        .map(|(line, word)| RejectedWord { line: *line, word: word.to_string() })
+        .collect::<Vec<_>>();
+    if let Some(first) = rejected.first() {
+        let message = format!("{} words aren't made of letters only, the first is {:?} on line {}", rejected.len(), first.word, first.line);
+        return Err(ApiError::new(ErrorCode::BadRequest, message)
+            .with_detail(rejected.into_iter().take(MAX_REJECTED_WORDS).collect::<Vec<_>>())
+            .into());
+    }
+
+    let words = lines.iter().map(|(_, word)| word.to_string()).collect::<Vec<_>>();
+    let language = match &query.language {
+        Some(name) => {
+            let state = corpora.language_states().into_iter().find(|(l, _)| l == name)
+                .ok_or_else(|| bad_request(format!("Unknown language: {:?}", name)))?.1;
+            let alphabet = state.alphabet.clone().folding_diacritics(query.fold_diacritics);
+            if let Some(word) = words.iter().find(|w| !alphabet.word(w).chars().all(|c| alphabet.is_letter(c))) {
+                return Err(bad_request(format!("{:?} isn't made of letters of the {} alphabet", word, name)));
+            }
+            Some((name.clone(), state))
+        },
+        None => None,
+    };
+    let languages = corpora.language_states();
+    let fold_diacritics = query.fold_diacritics;
+    let settings = corpora.settings.clone();
+    let (language, state) = web::block(move || {
+            let language = language.or_else(|| {
+                let words = words.iter().map(String::as_str).collect::<Vec<_>>();
+                let candidates = languages.iter().map(|(name, state)| (name.as_str(), &state.alphabet, &state.profile));
+                let detected = language::detect(&words, candidates)?.0.to_string();
+                languages.iter().find(|(name, _)| *name == detected).cloned()
+            });
+            let alphabet = match &language {
+                Some((_, state)) if fold_diacritics => state.alphabet.clone().folding_diacritics(true),
+                Some((_, state)) => state.alphabet.clone(),
+                None => base,
+            };
+            let guesses = words.iter().map(|word| alphabet.word(word)).collect::<Vec<_>>();
+            let (corpus, answers) = group_words(guesses, None);
+            let mut state = index_state(&settings, alphabet, corpus, answers);
+            if let Some((_, language)) = &language {
+                state.frequencies = language.frequencies.clone();
+            }
+            (language.map(|(name, _)| name), state)
+        }).await
+        .map_err(|_| bad_request("Failed to index the word list"))?;
+    let words = state.corpus.iter().map(|(_, words)| words.len()).sum();
+    let id = format!("custom-{}", name);
+    let ttl = query.ttl.map(Duration::from_secs).or(corpora.upload_limits.default_ttl);
+    corpora.add_upload(&id, state, ttl).map_err(bad_request)?;
+    audit.record(&req, admin_token.name(&req).unwrap_or("anonymous"), "upload", format!("{} ({} words)", id, words));
+
+    Ok(Encoded(UploadedCorpus { id, words, language, expires_in: ttl.map(|t| t.as_secs()) }))
+}
+
+#[derive(Serialize)]
+struct Reloaded {
+    /// Words in the default corpus.
+    words: usize,
+    languages: Vec<String>,
+}
+
+impl Protobuf for Reloaded {}
+
+/// Reads every corpus from disk again and swaps them in at once, without a
+/// restart. Requests keep being served from the previous corpora meanwhile.
+#[post("/admin/reload")]
+async fn admin_reload(req: HttpRequest, admin_token: web::Data<AdminToken>, audit: web::Data<AuditLog>, corpora: web::Data<Corpora>) -> Result<impl Responder> {
+    let who = admin_token.authorize(&req)?;
+    let reloading = corpora.clone();
+    web::block(move || reloading.reload()).await
+        .map_err(|e| e.to_string())
+        .and_then(|reloaded| reloaded)
+        .map_err(|e| error(ErrorCode::ReloadFailed, format!("Failed to reload the corpora, the previous ones are still served: {}", e)))?;
+    audit.record(&req, &who, "reload", String::new());
+
+    Ok(Encoded(Reloaded {
+        words: corpora.default_state().corpus.iter().map(|(_, words)| words.len()).sum(),
+        languages: corpora.languages(),
+    }))
+}
+
+#[derive(Serialize)]
+struct ReloadedPastAnswers {
+    /// Past answers of the default corpus.
+    past_answers: usize,
+}
+
+impl Protobuf for ReloadedPastAnswers {}
+
+/// Reads the past answers of every corpus again, which is much cheaper than a
+/// full reload and enough when only that list grew.
+#[post("/admin/reload/past_answers")]
+async fn admin_reload_past_answers(req: HttpRequest, admin_token: web::Data<AdminToken>, audit: web::Data<AuditLog>, corpora: web::Data<Corpora>) -> Result<impl Responder> {
+    let who = admin_token.authorize(&req)?;
+    let reloading = corpora.clone();
+    let past_answers = web::block(move || reloading.reload_past_answers()).await
+        .map_err(|e| e.to_string())
+        .and_then(|reloaded| reloaded)
+        .map_err(|e| error(ErrorCode::ReloadFailed, format!("Failed to reload the past answers, the previous ones are still used: {}", e)))?;
+    audit.record(&req, &who, "reload_past_answers", String::new());
+
+    Ok(Encoded(ReloadedPastAnswers { past_answers }))
+}
+
+#[derive(Debug, Deserialize)]
+struct WordEditQuery {
+    /// The language to edit, the default corpus otherwise.
+    language: Option<String>,
+    /// Also write the change to the corpus files.
+    #[serde(default)]
+    persist: bool,
+}
+
+#[derive(Serialize)]
+struct EditedWord {
+    word: String,
+    /// Words in the corpus after the change.
+    words: usize,
+}
+
+impl Protobuf for EditedWord {}
+
+/// `groups` with `word` added at the end of its length, or removed.
+fn edit_group(groups: &WordsByLength, word: &str, add: bool) -> WordsByLength {
+    let n = word.chars().count();
+    let mut groups = groups.clone();
+    match groups.binary_search_by_key(&n, |(l, _)| *l) {
+        Ok(i) if add => groups[i].1.push(word.to_string()),
+        Ok(i) => groups[i].1.retain(|w| w != word),
+        Err(i) if add => groups.insert(i, (n, vec![word.to_string()])),
+        Err(_) => {},
+    }
+    groups.retain(|(_, words)| !words.is_empty());
+    groups
+}
+
+/// A copy of `state` with `word` added to the guesses, and to the answers too
+/// when they are the whole corpus, or removed from both. Caches start empty.
+fn edited_state(settings: &Settings, state: &AppState, word: &str, add: bool) -> AppState {
+    let corpus = edit_group(&state.corpus, word, add);
+    let answers = match add && state.separate_answers {
+        true => state.answers.clone(),
+        false => edit_group(&state.answers, word, add),
+    };
+    let mut edited = index_state(settings, state.alphabet.clone(), corpus, answers);
+    edited.separate_answers = state.separate_answers;
+    edited.opening_book = state.opening_book.clone();
+    edited.frequencies = state.frequencies.clone();
+    edited.past_answers = state.past_answers.clone();
+    edited.proper_nouns = state.proper_nouns.clone();
+    edited.denylist = state.denylist.clone();
+    edited
+}
+
+fn edit_word(req: &HttpRequest, admin_token: &AdminToken, audit: &AuditLog, corpora: &Corpora, spelling: &str, query: &WordEditQuery, add: bool) -> Result<EditedWord> {
+    let who = admin_token.authorize(req)?;
+    let edited = corpora.edit(query.language.as_deref(), |state, files| {
+        let word = state.alphabet.word(spelling);
+        if !word.chars().all(|c| state.alphabet.is_letter(c)) {
+            return Err(bad_request(format!("{:?} isn't made of letters of the alphabet", spelling)));
+        }
+        match (add, is_word(state, &word)) {
+            (true, true) => return Err(bad_request(format!("{} is already in the corpus", word))),
+            (false, false) => return Err(bad_request(format!("{} isn't in the corpus", word))),
+            _ => {},
+        }
+        if query.persist {
+            corpora::persist_word(files, &state.alphabet, spelling, &word, add)
+                .map_err(|e| error(ErrorCode::WriteFailed, format!("Failed to write the corpus, nothing was changed: {}", e)))?;
+        }
+        Ok(edited_state(&corpora.settings, state, &word, add))
+    })?;
+    let word = edited.alphabet.word(spelling);
+    let detail = format!("{} in {}{}", word, query.language.as_deref().unwrap_or("the default corpus"), if query.persist { ", persisted" } else { "" });
+    audit.record(req, &who, if add { "add_word" } else { "remove_word" }, detail);
+
+    Ok(EditedWord {
+        word,
+        words: edited.corpus.iter().map(|(_, words)| words.len()).sum(),
+    })
+}
+
+/// Adds a word missing from the corpus without a restart. It is a guess, and
+/// an answer too unless `ANSWERS_FILE` lists those apart; `persist=true`
+/// appends it to the corpus file as well.
+#[put("/admin/words/{word:[\\p{L}·]+}")]
+async fn admin_add_word(req: HttpRequest, path: web::Path<String>, query: web::Query<WordEditQuery>, admin_token: web::Data<AdminToken>, audit: web::Data<AuditLog>, corpora: web::Data<Corpora>) -> Result<impl Responder> {
+    Ok(Encoded(edit_word(&req, &admin_token, &audit, &corpora, &path, &query, true)?))
+}
+
+/// Removes a word from the guesses and the answers without a restart;
+/// `persist=true` removes it from the corpus and answers files as well.
+#[delete("/admin/words/{word:[\\p{L}·]+}")]
+async fn admin_remove_word(req: HttpRequest, path: web::Path<String>, query: web::Query<WordEditQuery>, admin_token: web::Data<AdminToken>, audit: web::Data<AuditLog>, corpora: web::Data<Corpora>) -> Result<impl Responder> {
+    Ok(Encoded(edit_word(&req, &admin_token, &audit, &corpora, &path, &query, false)?))
+}
+
+/// The feedback Wordle shows for `guess` when the answer is `answer`, using the
+/// same duplicate-letter rules as everything else. Neither word has to be in
+/// the word list.
+#[get("/api/grade/{answer:[\\p{L}·]+}/{guess:[\\p{L}·]+}")]
+async fn api_grade(path: web::Path<(String, String)>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let (answer, guess) = path.into_inner();
+    let (answer, guess) = (state.alphabet.word(&answer), state.alphabet.word(&guess));
+    if answer.chars().count() != guess.chars().count() {
+        return Err(error(ErrorCode::LengthMismatch, format!("Length mismatch: {} != {}", answer.chars().count(), guess.chars().count())));
+    }
+    let feedback = feedback::to_digits(&feedback::grade(&answer, &guess));
+
+    Ok(cached(&state, RouteClass::Static, Encoded(Grade { answer, guess, feedback })))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EmojiQuery {
+    /// Orange and blue squares instead of green and yellow.
+    #[serde(default)]
+    high_contrast: bool,
+}
+
+#[derive(Serialize)]
+struct EmojiGrid {
+    solved: bool,
+    text: String,
+}
+
+impl Protobuf for EmojiGrid {}
+
+/// The share text of a game against `answer`, graded with the same rules as
+/// `/api/grade`.
+#[get("/api/emoji/{answer:[\\p{L}·]+}/{guesses:[/\\p{L}·]+}")]
+async fn api_emoji(path: web::Path<(String, String)>, query: web::Query<EmojiQuery>, state: web::Data<AppState>) -> Result<impl Responder> {
+    let (answer, guesses) = path.into_inner();
+    let answer = state.alphabet.word(&answer);
+    let rows = guesses.split('/')
+        .filter(|g| !g.is_empty())
+        .map(|g| {
+            let guess = state.alphabet.word(g);
+            match guess.chars().count() == answer.chars().count() {
+                true => Ok(feedback::grade(&answer, &guess)),
+                false => Err(error(ErrorCode::LengthMismatch, format!("Length mismatch: {} != {}", guess.chars().count(), answer.chars().count()))),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(cached(&state, RouteClass::Static, Encoded(EmojiGrid {
+        solved: rows.last().is_some_and(|row| feedback::solved(row)),
+        text: share::render(&rows, MAX_GUESSES, query.high_contrast),
+    })))
+}
+
+/// Games that need more guesses than this count as failures in a benchmark.
+const MAX_GUESSES: usize = 6;
+const PROGRESS_INTERVAL: usize = 100;
+
+/// `wordler benchmark <length> [query]` simulates a game against every answer
+/// of the given length and prints the guess distribution. The optional query
+/// takes the `/api/simulate` parameters, e.g. `strategy=minimax&hard=true`.
+fn benchmark(state: &AppState, args: &[String]) {
+    let length = args.first().and_then(|n| n.parse().ok()).expect("Usage: wordler benchmark <length> [query]");
+    let query = args.get(1).map_or("", String::as_str);
+    let strategy = web::Query::<StrategyQuery>::from_query(query).expect("Invalid benchmark query");
+    let hard = web::Query::<HardMode>::from_query(query).expect("Invalid benchmark query");
+
+    let words = length_words(&state.answers, length);
+    let guesses = scoring::to_chars(length_words(&state.corpus, length));
+    let opener = strategy::best_guess(strategy.strategy, &guesses, &scoring::to_chars(words)).map(|g| g.iter().collect::<String>());
+    let mut distribution: HashMap<usize, usize> = HashMap::new();
+    let mut failures = Vec::new();
+    for (i, answer) in words.iter().enumerate() {
+        let simulation = simulate(state, answer, strategy.strategy, &hard, &ExcludeQuery::default(), None, opener.as_slice());
+        *distribution.entry(simulation.turns).or_default() += 1;
+        if !simulation.solved || simulation.turns > MAX_GUESSES {
+            failures.push(answer.as_str());
+        }
+        if (i + 1) % PROGRESS_INTERVAL == 0 {
+            eprintln!("benchmark: {}/{} games", i + 1, words.len());
+        }
+    }
+
+    let turns = distribution.iter().map(|(t, n)| t * n).sum::<usize>();
+    println!("games: {}", words.len());
+    println!("opener: {}", opener.unwrap_or_default());
+    println!("average: {:.3}", turns as f64 / words.len().max(1) as f64);
+    for (t, n) in distribution.iter().sorted() {
+        println!("{} guesses: {}", t, n);
+    }
+    println!("failures: {}", failures.join(", "));
+}
+
+/// Groups a word list by length, keeping the order of the words.
+type WordsByLength = Vec<(usize, Vec<String>)>;
+
+fn by_length<'a>(words: impl Iterator<Item = &'a str>) -> WordsByLength {
+    words
+        .map(|w| (w.chars().count(), w))
+        .sorted_by_key(|(l,_)| *l)
+        .group_by(|(l,_)| *l).into_iter()
+        .map(|(l, w)| (l, w.into_iter().map(|(_,w)| w).map(String::from).collect_vec()))
+        .collect()
+}
+
+fn env_parse<T: FromStr>(var: &str) -> Option<T> {
+    env::var(var).ok()
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value for {}: {:?}", var, value)))
+}
+
+/// Invalid entries quoted in the clean-up summary of a word list.
+const MAX_REPORTED_ENTRIES: usize = 5;
+
+/// The words of a word list file, trimmed and normalized, without blank lines,
+/// duplicates, or entries with anything but letters of the alphabet, such as
+/// digits or punctuation. What was dropped is logged.
+fn clean_words(alphabet: &Alphabet, path: &str, text: &str) -> Vec<String> {
+    let (mut blank, mut duplicates) = (0, 0);
+    let mut invalid = Vec::new();
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            blank += 1;
+            continue;
+        }
+        let word = alphabet.word(line);
+        if !word.chars().all(|c| alphabet.is_letter(c)) {
+            invalid.push(line);
+        } else if !seen.insert(word.clone()) {
+            duplicates += 1;
+        } else {
+            words.push(word);
+        }
+    }
+    if blank + duplicates + invalid.len() > 0 {
+        eprintln!("corpus {}: kept {} words, dropped {} blank lines, {} duplicates and {} invalid entries {:?}",
+            path, words.len(), blank, duplicates, invalid.len(), invalid.iter().take(MAX_REPORTED_ENTRIES).collect::<Vec<_>>());
+    }
+    words
+}
+
+/// What every corpus is read and indexed with besides its files, read from
+/// the environment once at startup.
+#[derive(Debug, Clone)]
+struct Settings {
+    case: CaseFolding,
+    snapshot_dir: Option<String>,
+    static_max_age: Option<u32>,
+    dynamic_max_age: Option<u32>,
+    slow_query: Option<Duration>,
+    tree_max_nodes: usize,
+    lookahead_candidates: usize,
+    costs_candidates: usize,
+    valid_max_words: usize,
+    optimal_candidates: usize,
+    optimal_depth: usize,
+    optimal_time_limit: Duration,
+}
+
+impl Settings {
+    fn from_env() -> Settings {
+        Settings {
+            case: env_parse("CASE_FOLDING").unwrap_or_default(),
+            snapshot_dir: env::var("CORPUS_SNAPSHOT_DIR").ok(),
+            static_max_age: env_parse("CACHE_MAX_AGE_STATIC"),
+            dynamic_max_age: env_parse("CACHE_MAX_AGE_DYNAMIC"),
+            slow_query: env_parse("SLOW_QUERY_MS").map(Duration::from_millis),
+            tree_max_nodes: env_parse("TREE_MAX_NODES").unwrap_or(DEFAULT_TREE_MAX_NODES),
+            lookahead_candidates: env_parse("LOOKAHEAD_MAX_CANDIDATES").unwrap_or(DEFAULT_LOOKAHEAD_CANDIDATES),
+            costs_candidates: env_parse("COSTS_MAX_CANDIDATES").unwrap_or(DEFAULT_COSTS_CANDIDATES),
+            valid_max_words: env_parse("VALID_MAX_WORDS").unwrap_or(DEFAULT_VALID_MAX_WORDS),
+            optimal_candidates: env_parse("OPTIMAL_MAX_CANDIDATES").unwrap_or(DEFAULT_OPTIMAL_CANDIDATES),
+            optimal_depth: env_parse("OPTIMAL_MAX_DEPTH").unwrap_or(DEFAULT_OPTIMAL_DEPTH).max(1),
+            optimal_time_limit: Duration::from_millis(env_parse("OPTIMAL_TIME_LIMIT_MS").unwrap_or(DEFAULT_OPTIMAL_TIME_LIMIT_MS)),
+        }
+    }
+}
+
+/// Reads and indexes a corpus with the same `settings` as every other corpus.
+/// Fails on a file that can't be read or parsed.
+fn load_state(files: &CorpusFiles, settings: &Settings) -> Result<AppState, String> {
+    if let Some(url) = &files.url {
+        remote::refresh(url, &files.corpus)?;
+    }
+    let alphabet = match &files.alphabet {
+        Some(path) => Alphabet::parse(&read_list(path, "alphabet file")?, settings.case)?,
+        None => Alphabet::new(settings.case),
+    }.folding_diacritics(files.fold_diacritics);
+
+    let snapshot = settings.snapshot_dir.as_ref()
+        .filter(|_| files.corpus != corpora::EMBEDDED)
+        .map(|dir| (snapshot::path(dir, files), snapshot::key(files, settings.case)));
+    if let Some(cached) = snapshot.as_ref().and_then(|(path, key)| snapshot::read(path, key)) {
+        eprintln!("corpus {}: loaded from snapshot {}", files.corpus, snapshot.map_or(String::new(), |(path, _)| path));
+        let mut state = index_state(settings, alphabet, cached.corpus, cached.answers);
+        state.frequencies = cached.frequencies;
+        return load_word_lists(state, files);
+    }
+
+    let guesses = clean_words(&alphabet, &files.corpus, &read_list(&files.corpus, "corpus")?);
+    let answers = match &files.answers {
+        Some(path) => Some(clean_words(&alphabet, path, &read_list(path, "answers file")?)),
+        None => None,
+    };
+    let (corpus, answers) = group_words(guesses, answers);
+    let frequencies = files.frequencies.as_ref().map_or(Ok(HashMap::new()), |path| load_frequencies(&alphabet, path))?;
+    if let Some((path, key)) = snapshot {
+        if let Err(e) = snapshot::write(&path, key, &corpus, &answers, &frequencies) {
+            eprintln!("corpus {}: failed to write snapshot {}: {}", files.corpus, path, e);
+        }
+    }
+    let mut state = index_state(settings, alphabet, corpus, answers);
+    state.frequencies = frequencies;
+    load_word_lists(state, files)
+}
+
+/// Adds the opening book, past answers, proper nouns and denylist of `files`,
+/// which aren't kept in snapshots, and records whether it has its own answers.
+fn load_word_lists(mut state: AppState, files: &CorpusFiles) -> Result<AppState, String> {
+    state.separate_answers = files.answers.is_some();
+    state.opening_book = files.opening_book.as_ref().map_or(Ok(HashMap::new()), |path| load_opening_book(&state.alphabet, path))?;
+    state.past_answers = PastAnswers::load(&state.alphabet, files.past_answers.clone())?;
+    state.proper_nouns = files.proper_nouns.as_ref().map_or(Ok(HashSet::new()), |path| load_word_set(&state.alphabet, path))?;
+    state.denylist = files.denylist.as_ref().map_or(Ok(HashSet::new()), |path| load_word_set(&state.alphabet, path))?;
+    Ok(state)
+}
+
+fn read_list(path: &str, what: &str) -> Result<String, String> {
+    corpora::read_file(path).map_err(|e| format!("Failed to read {} {}: {}", what, path, e))
+}
+
+fn load_word_set(alphabet: &Alphabet, path: &str) -> Result<HashSet<String>, String> {
+    Ok(clean_words(alphabet, path, &read_list(path, "word list")?).into_iter().collect())
+}
+
+/// Every word by length, and the answers by length: all the words when there
+/// is no separate answer list.
+fn group_words(guesses: Vec<String>, answers: Option<Vec<String>>) -> (WordsByLength, WordsByLength) {
+    let corpus = by_length(guesses.iter().chain(answers.iter().flatten()).map(String::as_str).unique());
+    let answers = answers.map_or(corpus.clone(), |a| by_length(a.iter().map(String::as_str)));
+    (corpus, answers)
+}
+
+/// Indexes words grouped by length, with none of the word lists
+/// `load_word_lists` adds and no frequencies.
+fn index_state(settings: &Settings, mut alphabet: Alphabet, corpus: WordsByLength, answers: WordsByLength) -> AppState {
+    alphabet.derive_letters(corpus.iter().flat_map(|(_, words)| words.iter().map(String::as_str)));
+    let most_common = PerLength::new(&answers);
+    let positional = PerLength::new(&answers);
+    let word_ids = PerLength::new(&corpus);
+    let answer_sets = PerLength::new(&answers);
+    let neighbors = PerLength::new(&corpus);
+    let profile = language::profile(&alphabet, corpus.iter().flat_map(|(_, words)| words.iter().map(String::as_str)));
+    let openers = Mutex::new(HashMap::new());
+
+    AppState {
+        corpus,
+        alphabet,
+        answers,
+        separate_answers: false,
+        most_common,
+        positional,
+        word_ids,
+        answer_sets,
+        neighbors,
+        profile,
+        opening_book: HashMap::new(),
+        frequencies: HashMap::new(),
+        past_answers: PastAnswers::default(),
+        proper_nouns: HashSet::new(),
+        denylist: HashSet::new(),
+        openers,
+        subtrees: Mutex::new(HashMap::new()),
+        tree_max_nodes: settings.tree_max_nodes,
+        slow_query: settings.slow_query,
+        lookahead_candidates: settings.lookahead_candidates,
+        costs_candidates: settings.costs_candidates,
+        valid_max_words: settings.valid_max_words,
+        optimal_candidates: settings.optimal_candidates,
+        optimal_depth: settings.optimal_depth,
+        optimal_time_limit: settings.optimal_time_limit,
+        static_max_age: settings.static_max_age,
+        dynamic_max_age: settings.dynamic_max_age,
+    }
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let corpora = web::Data::new(Corpora::load(CorpusFiles::from_env(), env::var("LANGUAGES_DIR").ok(), Settings::from_env()).unwrap_or_else(|e| panic!("{}", e)));
+    let admin_token = web::Data::new(AdminToken::from_env());
+    let export_key = web::Data::new(ExportKey::from_env());
+    strategy::Adaptive::init();
+    let audit = web::Data::new(AuditLog::open(env::var("AUDIT_LOG_FILE").ok().as_deref()).unwrap_or_else(|e| panic!("{}", e)));
+    let payload_limit = env_parse::<usize>("PAYLOAD_LIMIT");
+    let base_path = env::var("BASE_PATH").ok()
+        .map(|p| p.trim_matches('/').to_string())
+        .filter(|p| !p.is_empty())
+        .map_or(String::new(), |p| format!("/{}", p));
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("benchmark") {
+        benchmark(&corpora.default_state(), &args[1..]);
+        return Ok(());
+    }
+
+    // The addresses `ADMIN_BIND` listens on, known once bound. Admin routes
+    // are only served there when it is set.
+    let admin_addrs = Arc::new(OnceLock::<Vec<SocketAddr>>::new());
+    let bound_admin_addrs = admin_addrs.clone();
+    let admin_bind = env::var("ADMIN_BIND").ok().filter(|b| !b.trim().is_empty());
+    let mut server = HttpServer::new(move || {
+        let mut app = App::new();
+        let mut json = web::JsonConfig::default().error_handler(|e, _| bad_request(e));
+        if let Some(limit) = payload_limit {
+            json = json.limit(limit);
+            app = app.app_data(web::PayloadConfig::new(limit));
+        }
+        app
+            .app_data(json)
+            .app_data(web::QueryConfig::default().error_handler(|e, _| bad_request(e)))
+            .app_data(web::PathConfig::default().error_handler(|e, _| bad_request(e)))
+            .app_data(corpora.clone())
+            .app_data(admin_token.clone())
+            .app_data(export_key.clone())
+            .app_data(audit.clone())
+            .wrap_fn({
+                let (corpora, base_path) = (corpora.clone(), base_path.clone());
+                move |mut req, srv| {
+                    let response = corpora.select(&mut req, &base_path).map(|_| srv.call(req));
+                    async move { response?.await }
+                }
+            })
+            .service(web::scope(&base_path)
+                .service(api_words)
+                .service(api_words_history)
+                .service(api_words_query)
+                .service(api_batch)
+                .service(api_constraints)
+                .service(api_state)
+                .service(api_resume)
+                .service(api_export)
+                .service(api_import)
+                .service(api_share)
+                .service(api_any_length)
+                .service(api_soft)
+                .service(api_clusters)
+                .service(api_solve)
+                .service(api_progress)
+                .service(api_positions)
+                .service(api_hint)
+                .service(api_pareto)
+                .service(api_suggest)
+                .service(api_partitions)
+                .service(api_simulate)
+                .service(api_absurdle)
+                .service(api_costs)
+                .service(api_tree)
+                .service(api_antiwordle)
+                .service(api_fibble)
+                .service(api_explain)
+                .service(api_multi_words)
+                .service(api_most_letters)
+                .service(api_most_common)
+                .service(api_fresh)
+                .service(api_openers)
+                .service(api_valid)
+                .service(api_valid_bulk)
+                .service(api_ladder)
+                .service(api_grade)
+                .service(api_emoji)
+                .service(api_validate)
+                .service(api_languages)
+                .service(api_anagrams)
+                .service(api_match)
+                .service(api_regex)
+                .service(api_corpus_stats)
+                .service(api_upload_corpus)
+                .service(web::scope("")
+                    .wrap_fn({
+                        let admin_addrs = admin_addrs.clone();
+                        move |req, srv| {
+                            let local = req.app_config().local_addr();
+                            let served = admin_addrs.get().is_none_or(|addrs| addrs.contains(&local));
+                            let response = served.then(|| srv.call(req)).ok_or_else(|| actix_web::error::ErrorNotFound("Not Found"));
+                            async move { response?.await }
+                        }
+                    })
+                    .service(admin_reload)
+                    .service(admin_reload_past_answers)
+                    .service(admin_add_word)
+                    .service(admin_remove_word)
+                    .service(admin_audit)
+                )
+            )
+    });
+    if let Some(secs) = env_parse("KEEP_ALIVE_SECONDS") {
+        server = server.keep_alive(Duration::from_secs(secs));
+    }
+    if let Some(ms) = env_parse("CLIENT_REQUEST_TIMEOUT_MS") {
+        server = server.client_request_timeout(Duration::from_millis(ms));
+    }
+    if let Some(n) = env_parse("MAX_CONNECTIONS") {
+        server = server.max_connections(n);
+    }
+    if let Some(n) = env_parse("BACKLOG") {
+        server = server.backlog(n);
+    }
+
+    let binds = env::var("BIND").unwrap_or("0.0.0.0:8080".to_string());
+    for addr in binds.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        server = server.bind(addr)?;
+    }
+    if let Some(admin_bind) = admin_bind {
+        let public = server.addrs();
+        for addr in admin_bind.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            server = server.bind(addr)?;
+        }
+        let _ = bound_admin_addrs.set(server.addrs().into_iter().filter(|a| !public.contains(a)).collect());
+    }
+
+    server.run().await
 }
\ No newline at end of file
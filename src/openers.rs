@@ -0,0 +1,67 @@
+use itertools::Itertools;
+
+/// Letters of a word as a bitmask over `alphabet`, or `None` if the word uses
+/// a letter outside of it.
+fn letter_mask(alphabet: &[char], word: &str) -> Option<u64> {
+    word.chars().try_fold(0, |mask, c| alphabet.iter().position(|a| *a == c).map(|i| mask | 1 << i))
+}
+
+fn mask_score(weights: &[usize], mask: u64) -> usize {
+    weights.iter().enumerate().filter(|(i, _)| mask & (1 << i) != 0).map(|(_, w)| w).sum()
+}
+
+/// The `limit` best sets of `size` words by letter coverage: the sum of the
+/// weights of the distinct letters they contain, where `weights` is usually how
+/// many words contain each letter. Returns indices into `words`, best first.
+///
+/// Words with the same letters are only tried once, and the search stops
+/// descending as soon as even the best unused words couldn't beat the current
+/// `limit`-th best set.
+pub fn best_openers(words: &[String], weights: &[(char, usize)], size: usize, limit: usize) -> Vec<(usize, Vec<usize>)> {
+    let alphabet = weights.iter().map(|(c, _)| *c).take(64).collect::<Vec<_>>();
+    let weights = weights.iter().map(|(_, w)| *w).take(64).collect::<Vec<_>>();
+    let singles = words.iter().enumerate()
+        .filter_map(|(id, w)| letter_mask(&alphabet, w).map(|m| (m, id)))
+        .unique_by(|(m, _)| *m)
+        .map(|(m, id)| (mask_score(&weights, m), m, id))
+        .sorted_by_key(|(s, _, _)| std::cmp::Reverse(*s))
+        .collect::<Vec<_>>();
+
+    let mut best = Vec::new();
+    let mut chosen = Vec::new();
+    search(&singles, &weights, size, limit, 0, 0, &mut chosen, &mut best);
+    best
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search(
+    singles: &[(usize, u64, usize)],
+    weights: &[usize],
+    size: usize,
+    limit: usize,
+    from: usize,
+    mask: u64,
+    chosen: &mut Vec<usize>,
+    best: &mut Vec<(usize, Vec<usize>)>,
+) {
+    if chosen.len() == size {
+        let score = mask_score(weights, mask);
+        let at = best.partition_point(|(s, _)| *s >= score);
+        if at < limit {
+            best.insert(at, (score, chosen.clone()));
+            best.truncate(limit);
+        }
+        return;
+    }
+    let left = size - chosen.len();
+    let current = mask_score(weights, mask);
+    for i in from..singles.len() {
+        let (single, m, id) = singles[i];
+        if best.len() == limit && current + single * left <= best[limit - 1].0 {
+            break;
+        }
+        chosen.push(id);
+        search(singles, weights, size, limit, i + 1, mask | m, chosen, best);
+        chosen.pop();
+    }
+}
@@ -0,0 +1,234 @@
+use std::{collections::HashMap, time::Instant};
+use itertools::Itertools;
+
+use crate::feedback::{self, Tile};
+use crate::wordset::WordSet;
+
+/// Limits of the exhaustive search.
+#[derive(Debug, Copy, Clone)]
+pub struct Limits {
+    /// Every answer must be found within this many guesses.
+    pub max_depth: usize,
+    pub deadline: Instant,
+}
+
+/// Why the search gave up.
+#[derive(Debug)]
+pub enum SearchError {
+    TimedOut,
+    TooDeep,
+}
+
+/// Fewest expected guesses to find one of `n` answers: guess one of them and,
+/// if it is wrong, have the feedback single out the answer.
+fn lower_bound(n: usize) -> f64 {
+    (2 * n - 1) as f64 / n as f64
+}
+
+/// Candidates that get the same feedback, with how many there are.
+type Bucket = (usize, WordSet);
+
+struct Search<'a> {
+    guesses: &'a [Vec<char>],
+    answers: &'a [Vec<char>],
+    limits: Limits,
+    /// Exact results by candidate set and guesses left.
+    solved: HashMap<(WordSet, usize), Option<f64>>,
+    timed_out: bool,
+}
+
+impl Search<'_> {
+    /// The feedback buckets of every useful guess, with a lower bound on the
+    /// expected guesses when playing it, most promising first.
+    fn options(&self, ids: &WordSet) -> Vec<(usize, f64, Vec<Bucket>)> {
+        let size = ids.len();
+        let n = size as f64;
+        self.guesses.iter().enumerate()
+            .filter_map(|(g, guess)| {
+                let mut buckets: HashMap<Vec<Tile>, Bucket> = HashMap::new();
+                for id in ids.iter() {
+                    let (len, bucket) = buckets.entry(feedback::grade_chars(&self.answers[id], guess))
+                        .or_insert_with(|| (0, WordSet::from_ids(ids.length(), self.answers.len(), [])));
+                    *len += 1;
+                    bucket.insert(id);
+                }
+                let buckets = buckets.into_iter()
+                    .filter(|(f, _)| !feedback::solved(f))
+                    .map(|(_, b)| b)
+                    .sorted_by_key(|(len, _)| std::cmp::Reverse(*len))
+                    .collect::<Vec<_>>();
+                if buckets.first().is_some_and(|(len, _)| *len == size) {
+                    return None;
+                }
+                let bound = 1.0 + buckets.iter().map(|(len, _)| *len as f64 / n * lower_bound(*len)).sum::<f64>();
+                Some((g, bound, buckets))
+            })
+            .sorted_by(|(_, a, _), (_, b, _)| a.total_cmp(b))
+            .collect()
+    }
+
+    /// Expected guesses when playing a guess with these `buckets` first, if
+    /// below `beta`.
+    fn evaluate(&mut self, n: usize, bound: f64, buckets: &[Bucket], depth: usize, beta: f64) -> Option<f64> {
+        let mut value = bound;
+        for (len, bucket) in buckets {
+            let p = *len as f64 / n as f64;
+            let child_beta = lower_bound(*len) + (beta - value) / p;
+            let child = self.solve(bucket.clone(), *len, depth - 1, child_beta)?;
+            value += p * (child - lower_bound(*len));
+            if value >= beta {
+                return None;
+            }
+        }
+        Some(value)
+    }
+
+    /// Exact expected guesses to find the answer among the `size` words of
+    /// `ids` with `depth` guesses left, if below `beta`.
+    fn solve(&mut self, ids: WordSet, size: usize, depth: usize, beta: f64) -> Option<f64> {
+        if size == 1 {
+            return (depth >= 1 && beta > 1.0).then_some(1.0);
+        }
+        if depth < 2 || self.timed_out {
+            return None;
+        }
+        if Instant::now() > self.limits.deadline {
+            self.timed_out = true;
+            return None;
+        }
+        if let Some(value) = self.solved.get(&(ids.clone(), depth)) {
+            return value.filter(|v| *v < beta);
+        }
+
+        let mut best = None;
+        let mut cutoff = beta;
+        for (_, bound, buckets) in self.options(&ids) {
+            if bound >= cutoff {
+                break;
+            }
+            if let Some(value) = self.evaluate(size, bound, &buckets, depth, cutoff) {
+                best = Some(value);
+                cutoff = value;
+            }
+        }
+        if (best.is_some() || beta == f64::INFINITY) && !self.timed_out {
+            self.solved.insert((ids, depth), best);
+        }
+        best
+    }
+}
+
+/// The `limit` guesses with the fewest expected guesses to find the answer,
+/// including themselves, when every later guess is also played optimally.
+/// Returns indices into `guesses` with their exact values, best first.
+pub fn best_guesses(guesses: &[Vec<char>], answers: &[Vec<char>], limits: Limits, limit: usize) -> Result<Vec<(usize, f64)>, SearchError> {
+    let mut search = Search { guesses, answers, limits, solved: HashMap::new(), timed_out: false };
+    let ids = WordSet::from_fn(answers.first().map_or(0, Vec::len), answers.len(), |_| true);
+    let mut best: Vec<(usize, f64)> = Vec::new();
+    if answers.is_empty() {
+        return Ok(best);
+    }
+
+    for (g, bound, buckets) in search.options(&ids) {
+        let beta = match best.len() < limit {
+            true => f64::INFINITY,
+            false => best[limit - 1].1,
+        };
+        if bound >= beta {
+            break;
+        }
+        if let Some(value) = search.evaluate(answers.len(), bound, &buckets, limits.max_depth, beta) {
+            let at = best.partition_point(|(_, v)| *v <= value);
+            best.insert(at, (g, value));
+            best.truncate(limit);
+        }
+        if search.timed_out {
+            return Err(SearchError::TimedOut);
+        }
+    }
+    match best.is_empty() {
+        true => Err(SearchError::TooDeep),
+        false => Ok(best),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn words(list: &[&str]) -> Vec<Vec<char>> {
+        list.iter().map(|w| w.chars().collect()).collect()
+    }
+
+    fn limits(max_depth: usize) -> Limits {
+        Limits { max_depth, deadline: Instant::now() + Duration::from_secs(60) }
+    }
+
+    /// Expected guesses with `guess` played first and the best guess after
+    /// every feedback, trying every guess at every step. Only memoized.
+    fn exhaustive(guesses: &[Vec<char>], answers: &[Vec<char>], guess: &[char], depth: usize, memo: &mut HashMap<(Vec<Vec<char>>, usize), Option<f64>>) -> Option<f64> {
+        if depth == 0 {
+            return None;
+        }
+        let mut buckets: HashMap<Vec<Tile>, Vec<Vec<char>>> = HashMap::new();
+        for answer in answers {
+            buckets.entry(feedback::grade_chars(answer, guess)).or_default().push(answer.clone());
+        }
+        let mut value = 1.0;
+        for (feedback, bucket) in buckets {
+            if feedback::solved(&feedback) {
+                continue;
+            }
+            let p = bucket.len() as f64 / answers.len() as f64;
+            let key = (bucket, depth - 1);
+            let best = match memo.get(&key) {
+                Some(best) => *best,
+                None => {
+                    let best = guesses.iter()
+                        .filter_map(|g| exhaustive(guesses, &key.0, g, depth - 1, memo))
+                        .min_by(f64::total_cmp);
+                    memo.insert(key, best);
+                    best
+                },
+            };
+            value += p * best?;
+        }
+        Some(value)
+    }
+
+    #[test]
+    fn probe_that_splits_every_answer() {
+        // Guessing an answer leaves the other three together, for
+        // (1 + 3 * (1 + 2)) / 4 = 2.5 guesses; CHM tells them all apart.
+        let answers = words(&["bat", "cat", "hat", "mat"]);
+        let guesses = words(&["bat", "cat", "hat", "mat", "chm"]);
+        let best = best_guesses(&guesses, &answers, limits(6), 5).unwrap();
+        assert_eq!(best[0], (4, 2.0));
+        assert!(best[1..].iter().all(|(_, value)| (value - 2.5).abs() < 1e-9), "{:?}", best);
+    }
+
+    #[test]
+    fn too_deep() {
+        let answers = words(&["bat", "cat", "hat", "mat"]);
+        assert!(matches!(best_guesses(&answers, &answers, limits(2), 5), Err(SearchError::TooDeep)));
+    }
+
+    #[test]
+    fn pruning_matches_exhaustive_search() {
+        let all = itertools::repeat_n("abc".chars(), 3).multi_cartesian_product().collect::<Vec<_>>();
+        for step in [2, 3, 5] {
+            let answers = all.iter().step_by(step).cloned().collect::<Vec<_>>();
+            let mut memo = HashMap::new();
+            let values = all.iter().map(|g| exhaustive(&all, &answers, g, 4, &mut memo)).collect::<Vec<_>>();
+            let best = best_guesses(&all, &answers, limits(4), all.len()).unwrap();
+            for (g, value) in &best {
+                let expected = values[*g].unwrap();
+                assert!((value - expected).abs() < 1e-9, "guess {:?}: {} != {}", all[*g], value, expected);
+            }
+            let optimum = values.iter().flatten().copied().min_by(f64::total_cmp).unwrap();
+            assert!((best[0].1 - optimum).abs() < 1e-9, "{} != {}", best[0].1, optimum);
+        }
+    }
+}
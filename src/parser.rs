@@ -0,0 +1,369 @@
+use std::fmt;
+use serde::Serialize;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{alphabet::Alphabet, error::{ApiError, ErrorCode}, feedback::Tile};
+
+#[derive(Debug, Copy, Clone)]
+pub enum LetterAnswerType {
+    /// A tile of the game's feedback.
+    Graded(Tile),
+    /// In the word, but whether this position was correct is unknown.
+    Present,
+    /// A wildcard: nothing is known about this tile.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct LetterAnswer {
+    pub letter: char,
+    pub answer: LetterAnswerType,
+    /// The score was prefixed with `~`: the user isn't sure of this color.
+    pub uncertain: bool,
+}
+
+pub type WordAnswer = Vec<LetterAnswer>;
+
+/// Writes a parsed token back in its canonical `CRANE21000` form.
+pub fn canonical_token(answer: &WordAnswer) -> String {
+    let letters = answer.iter().map(|a| match a.answer {
+        LetterAnswerType::Unknown => '.',
+        _ => a.letter,
+    });
+    let scores = answer.iter().flat_map(|a| {
+        let score = match a.answer {
+            LetterAnswerType::Graded(tile) => tile.digit(),
+            LetterAnswerType::Present => '3',
+            LetterAnswerType::Unknown => '.',
+        };
+        a.uncertain.then_some('~').into_iter().chain([score])
+    });
+    letters.chain(scores).collect()
+}
+
+/// Why a clue token couldn't be parsed. Offsets count characters from the
+/// start of the token, separators included.
+#[derive(Debug, Serialize)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum ParseError {
+    BadLetter { token: String, offset: usize, found: char },
+    BadScore { token: String, offset: usize, found: char },
+    MissingLetters { token: String },
+    MissingScores { token: String },
+    LengthMismatch { token: String, letters: usize, scores: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::BadLetter { token, offset, found } =>
+                write!(f, "Invalid token: {:?}, {:?} at offset {} is not a letter", token, found, offset),
+            ParseError::BadScore { token, offset, found } =>
+                write!(f, "Invalid token: {:?}, {:?} at offset {} is not a score (0-3)", token, found, offset),
+            ParseError::MissingLetters { token } =>
+                write!(f, "Invalid token: {:?}, missing letters", token),
+            ParseError::MissingScores { token } =>
+                write!(f, "Invalid token: {:?}, missing scores", token),
+            ParseError::LengthMismatch { token, letters, scores } =>
+                write!(f, "Invalid token: {:?}, there are {} letters and {} scores", token, letters, scores),
+        }
+    }
+}
+
+impl From<ParseError> for ApiError {
+    fn from(e: ParseError) -> Self {
+        let code = match e {
+            ParseError::LengthMismatch { .. } => ErrorCode::LengthMismatch,
+            _ => ErrorCode::BadToken,
+        };
+        ApiError::new(code, &e).with_detail(e)
+    }
+}
+
+/// A `ParseError` of one token in a multi-guess path. Guesses count from 1.
+#[derive(Debug, Serialize)]
+pub struct TokenError {
+    pub guess: usize,
+    #[serde(flatten)]
+    pub error: ParseError,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Guess {}: {}", self.guess, self.error)
+    }
+}
+
+/// Every malformed token at once, so that each offending guess can be pointed
+/// out; the code is the one of the first error.
+impl From<Vec<TokenError>> for ApiError {
+    fn from(errors: Vec<TokenError>) -> Self {
+        let code = match errors.first() {
+            Some(TokenError { error: ParseError::LengthMismatch { .. }, .. }) => ErrorCode::LengthMismatch,
+            _ => ErrorCode::BadToken,
+        };
+        ApiError::new(code, errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")).with_detail(errors)
+    }
+}
+
+fn is_placeholder(c: char) -> bool {
+    matches!(c, '.' | '-' | '_' | '?')
+}
+
+fn is_separator(c: char) -> bool {
+    c == ' ' || c == ','
+}
+
+fn score(c: char) -> Option<LetterAnswerType> {
+    Tile::from_digit(c).map(LetterAnswerType::Graded).or(match c {
+        '3' => Some(LetterAnswerType::Present),
+        c if is_placeholder(c) => Some(LetterAnswerType::Unknown),
+        _ => None,
+    })
+}
+
+/// The score digit of a color initial: green, yellow or black.
+fn color_score(c: char) -> Option<char> {
+    let tile = match c.to_ascii_lowercase() {
+        'g' => Tile::Correct,
+        'y' => Tile::Misplaced,
+        'b' => Tile::Absent,
+        _ => return None,
+    };
+    Some(tile.digit())
+}
+
+/// Feedback written with color initials (`gybbb`) as score digits.
+pub fn color_digits(feedback: &str) -> Option<String> {
+    feedback.chars().map(color_score).collect::<Option<String>>().filter(|digits| !digits.is_empty())
+}
+
+/// Rewrites a token of letters paired with color initials, such as
+/// `c.g r.y a.b n.b e.b`, in the `crane21000` form.
+fn dotted_colors(token: &str) -> Option<String> {
+    let pairs = token.split(is_separator).filter(|p| !p.is_empty())
+        .map(|p| match p.chars().collect::<Vec<_>>().as_slice() {
+            [letter, '.', color] => color_score(*color).map(|s| (*letter, s)),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|pairs| !pairs.is_empty())?;
+    Some(pairs.iter().map(|(l, _)| l).chain(pairs.iter().map(|(_, s)| s)).collect())
+}
+
+/// Splits a clue path into its tokens. Besides `crane21000`, a guess may be
+/// written with color initials, either as a word followed by its colors
+/// (`crane/gybbb`) or letter by letter (`c.g r.y a.b n.b e.b`); both are
+/// rewritten with digits.
+pub fn split_tokens(pattern: &str) -> Vec<String> {
+    let parts = pattern.split('/').collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < parts.len() {
+        let word = !parts[i].is_empty() && parts[i].chars().all(|c| score(c).is_none() || is_placeholder(c));
+        let colors = parts.get(i + 1).filter(|_| word).and_then(|next| color_digits(next));
+        match colors {
+            Some(colors) => {
+                tokens.push(format!("{}{}", parts[i], colors));
+                i += 2;
+            },
+            None => {
+                tokens.push(dotted_colors(parts[i]).unwrap_or_else(|| parts[i].to_string()));
+                i += 1;
+            },
+        }
+    }
+    tokens
+}
+
+/// A significant character of a token, or a multi-character spelling of a
+/// tile.
+enum Unit {
+    Tile(char),
+    Placeholder(char),
+    Score(LetterAnswerType),
+    Tilde,
+    Other(char),
+}
+
+fn units(alphabet: &Alphabet, chars: &[char]) -> Vec<(usize, Unit)> {
+    let mut units = Vec::new();
+    let mut offset = 0;
+    while offset < chars.len() {
+        let c = chars[offset];
+        let (unit, len) = match alphabet.digraph(&chars[offset..]) {
+            _ if is_separator(c) => {
+                offset += 1;
+                continue;
+            },
+            Some((tile, len)) => (Unit::Tile(tile), len),
+            None if is_placeholder(c) => (Unit::Placeholder(c), 1),
+            None if alphabet.is_letter(c) => (Unit::Tile(alphabet.fold_letter(c)), 1),
+            None if c == '~' => (Unit::Tilde, 1),
+            None => (score(c).map_or(Unit::Other(c), Unit::Score), 1),
+        };
+        units.push((offset, unit));
+        offset += len;
+    }
+    units
+}
+
+/// Parses a `letters scores` token such as `crane21000`. Spaces and commas are
+/// ignored anywhere and letters are folded as the alphabet says. A wildcard
+/// (`.`, `-`, `_` or `?`) in either half stands for an unconstrained tile, as
+/// in `cr_ne22.00`. A `~` before a score marks it as uncertain
+/// (`crane2~1000`). Multi-character spellings of the alphabet count as a
+/// single tile.
+///
+/// Wildcards between the last letter and the first score digit could belong
+/// to either half, so they are split to give both halves the same length.
+pub fn extract_answer(alphabet: &Alphabet, token: &str) -> Result<WordAnswer, ParseError> {
+    let chars = token.nfc().collect::<Vec<_>>();
+    let units = units(alphabet, &chars);
+    let first_score = units.iter().position(|(_, u)| matches!(u, Unit::Score(_) | Unit::Tilde)).unwrap_or(units.len());
+
+    let mut uncertain = None;
+    for (i, (offset, unit)) in units.iter().enumerate() {
+        let offset = *offset;
+        match unit {
+            Unit::Other(c) if i < first_score && !c.is_ascii_digit() => {
+                return Err(ParseError::BadLetter { token: token.to_string(), offset, found: *c });
+            },
+            Unit::Other(c) => return Err(ParseError::BadScore { token: token.to_string(), offset, found: *c }),
+            Unit::Tile(_) if i > first_score => {
+                return Err(ParseError::BadScore { token: token.to_string(), offset, found: chars[offset] });
+            },
+            Unit::Score(_) | Unit::Tilde if i == 0 => return Err(ParseError::MissingLetters { token: token.to_string() }),
+            Unit::Tilde if uncertain.is_some() => return Err(ParseError::BadScore { token: token.to_string(), offset, found: '~' }),
+            Unit::Tilde => uncertain = Some(offset),
+            _ => uncertain = None,
+        }
+    }
+    if let Some(offset) = uncertain {
+        return Err(ParseError::BadScore { token: token.to_string(), offset, found: '~' });
+    }
+
+    let last_letter = units[..first_score].iter().rposition(|(_, u)| matches!(u, Unit::Tile(_))).map_or(0, |i| i + 1);
+    let total = units.iter().filter(|(_, u)| !matches!(u, Unit::Tilde)).count();
+    let split = (total / 2).clamp(last_letter, first_score);
+    let letters = units[..split].iter()
+        .filter_map(|(_, u)| match u {
+            Unit::Tile(c) | Unit::Placeholder(c) => Some(*c),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut scores = Vec::new();
+    let mut marked = false;
+    for (_, unit) in &units[split..] {
+        match unit {
+            Unit::Tilde => marked = true,
+            Unit::Score(s) => scores.push((*s, std::mem::take(&mut marked))),
+            Unit::Placeholder(_) => scores.push((LetterAnswerType::Unknown, std::mem::take(&mut marked))),
+            _ => {},
+        }
+    }
+
+    if letters.is_empty() {
+        return Err(ParseError::MissingLetters { token: token.to_string() });
+    }
+    if scores.is_empty() {
+        return Err(ParseError::MissingScores { token: token.to_string() });
+    }
+    if letters.len() != scores.len() {
+        return Err(ParseError::LengthMismatch { token: token.to_string(), letters: letters.len(), scores: scores.len() });
+    }
+
+    Ok(letters.into_iter().zip(scores).map(|(letter, (answer, uncertain))| match letter {
+        l if is_placeholder(l) => LetterAnswer { letter, answer: LetterAnswerType::Unknown, uncertain: false },
+        _ => LetterAnswer { letter, answer, uncertain },
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::CaseFolding;
+
+    fn parse(token: &str) -> Result<String, ParseError> {
+        extract_answer(&Alphabet::new(CaseFolding::Upper), token).map(|answer| canonical_token(&answer))
+    }
+
+    #[test]
+    fn accepted_formats() {
+        let cases = [
+            ("crane21000", "CRANE21000"),
+            ("CRANE21000", "CRANE21000"),
+            ("c r a n e 2 1 0 0 0", "CRANE21000"),
+            ("crane,21000", "CRANE21000"),
+            ("crane2~1000", "CRANE2~1000"),
+            ("crane/gybbb", "CRANE21000"),
+            ("crane/GYBBB", "CRANE21000"),
+            ("c.g r.y a.b n.b e.b", "CRANE21000"),
+            ("c.g,r.y,a.b,n.b,e.b", "CRANE21000"),
+            ("crane31000", "CRANE31000"),
+        ];
+        for (path, canonical) in cases {
+            let tokens = split_tokens(path);
+            assert_eq!(tokens.len(), 1, "{}", path);
+            assert_eq!(parse(&tokens[0]).unwrap(), canonical, "{}", path);
+        }
+    }
+
+    #[test]
+    fn paths_of_several_guesses() {
+        assert_eq!(split_tokens("crane21000/slate00102"), ["crane21000", "slate00102"]);
+        assert_eq!(split_tokens("crane/gybbb/slate/bbygb"), ["crane21000", "slate00120"]);
+        assert_eq!(split_tokens("crane/gybbb/slate00102"), ["crane21000", "slate00102"]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |token: &str| parse(token).unwrap_err();
+        assert!(matches!(error("crane2100"), ParseError::LengthMismatch { letters: 5, scores: 4, .. }));
+        assert!(matches!(error("cran21000"), ParseError::LengthMismatch { letters: 4, scores: 5, .. }));
+        assert!(matches!(error("crane"), ParseError::MissingScores { .. }));
+        assert!(matches!(error("21000"), ParseError::MissingLetters { .. }));
+        assert!(matches!(error("~21000"), ParseError::MissingLetters { .. }));
+        assert!(matches!(error("cr#ne21000"), ParseError::BadLetter { offset: 2, found: '#', .. }));
+        assert!(matches!(error("cr4ne21000"), ParseError::BadScore { offset: 2, found: '4', .. }));
+        assert!(matches!(error("crane21x00"), ParseError::BadScore { offset: 7, found: 'x', .. }));
+        assert!(matches!(error("crane21900"), ParseError::BadScore { offset: 7, found: '9', .. }));
+        assert!(matches!(error("crane2~~1000"), ParseError::BadScore { offset: 7, found: '~', .. }));
+        assert!(matches!(error("crane2100~"), ParseError::BadScore { offset: 9, found: '~', .. }));
+    }
+
+    #[test]
+    fn placeholders_in_the_scores() {
+        for i in 0..5 {
+            let mut scores = "21000".chars().collect::<Vec<_>>();
+            scores[i] = '.';
+            let scores = scores.into_iter().collect::<String>();
+            let mut letters = "CRANE".chars().collect::<Vec<_>>();
+            letters[i] = '.';
+            let letters = letters.into_iter().collect::<String>();
+            assert_eq!(parse(&format!("crane{}", scores)).unwrap(), format!("{}{}", letters, scores), "crane{}", scores);
+        }
+    }
+
+    #[test]
+    fn placeholders_in_the_letters() {
+        for i in 0..5 {
+            let mut letters = "CRANE".chars().collect::<Vec<_>>();
+            letters[i] = '.';
+            let letters = letters.into_iter().collect::<String>();
+            let mut scores = "21000".chars().collect::<Vec<_>>();
+            scores[i] = '.';
+            let scores = scores.into_iter().collect::<String>();
+            assert_eq!(parse(&format!("{}21000", letters.to_lowercase())).unwrap(), format!("{}{}", letters, scores));
+        }
+    }
+
+    #[test]
+    fn placeholders_on_both_sides_of_the_split() {
+        assert_eq!(parse("cran_.1000").unwrap(), ".RAN..100.");
+        assert_eq!(parse("crane.2000").unwrap(), ".RANE.2000");
+        assert_eq!(parse("crane2.000").unwrap(), "C.ANE2.000");
+        assert_eq!(parse("cr_ne22.00").unwrap(), "CR.NE22.00");
+        assert_eq!(parse("..........").unwrap(), "..........");
+    }
+}
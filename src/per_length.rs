@@ -0,0 +1,22 @@
+use std::sync::OnceLock;
+
+/// A table per word length, built from the words of that length the first
+/// time it is needed, so that lengths nobody asks for cost nothing.
+pub struct PerLength<T> {
+    tables: Vec<(usize, OnceLock<T>)>,
+}
+
+impl<T> PerLength<T> {
+    /// Room for a table for every length of `words`.
+    pub fn new<W>(words: &[(usize, W)]) -> Self {
+        PerLength { tables: words.iter().map(|(n, _)| (*n, OnceLock::new())).collect() }
+    }
+
+    /// The table for `length`, made with `build` unless it already was; `None`
+    /// when there are no words of that length.
+    pub fn get(&self, length: usize, build: impl FnOnce() -> T) -> Option<&T> {
+        self.tables.iter()
+            .find(|(n, _)| *n == length)
+            .map(|(_, table)| table.get_or_init(build))
+    }
+}
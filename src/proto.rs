@@ -0,0 +1,73 @@
+//! Hand-written counterparts of the messages in `proto/wordler.proto`.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct WordList {
+    #[prost(string, repeated, tag = "1")]
+    pub words: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct WordFrequency {
+    #[prost(string, tag = "1")]
+    pub word: String,
+    #[prost(uint64, optional, tag = "2")]
+    pub frequency: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct WordFrequencyList {
+    #[prost(message, repeated, tag = "1")]
+    pub words: Vec<WordFrequency>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Solution {
+    #[prost(string, repeated, tag = "1")]
+    pub candidates: Vec<String>,
+    #[prost(string, repeated, tag = "2")]
+    pub probes: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Suggestion {
+    #[prost(string, tag = "1")]
+    pub word: String,
+    #[prost(double, tag = "2")]
+    pub entropy: f64,
+    #[prost(uint64, tag = "3")]
+    pub worst_case: u64,
+    #[prost(double, tag = "4")]
+    pub expected_remaining: f64,
+    #[prost(double, optional, tag = "5")]
+    pub expected_guesses: Option<f64>,
+    #[prost(bool, tag = "6")]
+    pub candidate: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SuggestionList {
+    #[prost(message, repeated, tag = "1")]
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SimulatedTurn {
+    #[prost(string, tag = "1")]
+    pub guess: String,
+    #[prost(string, tag = "2")]
+    pub feedback: String,
+    #[prost(uint64, tag = "3")]
+    pub candidates: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Simulation {
+    #[prost(string, tag = "1")]
+    pub answer: String,
+    #[prost(uint64, tag = "2")]
+    pub turns: u64,
+    #[prost(bool, tag = "3")]
+    pub solved: bool,
+    #[prost(message, repeated, tag = "4")]
+    pub guesses: Vec<SimulatedTurn>,
+}
@@ -0,0 +1,52 @@
+use std::{env, fs, io, path::Path};
+
+/// The response headers kept next to a cached download, to ask the server
+/// whether it changed.
+const VALIDATORS: [(&str, &str); 2] = [("ETag", "If-None-Match"), ("Last-Modified", "If-Modified-Since")];
+
+/// Where a corpus downloaded from `CORPUS_URL` is kept when `CORPUS_FILE`
+/// doesn't say.
+pub fn default_cache() -> String {
+    env::temp_dir().join("wordler-corpus.txt").to_string_lossy().into_owned()
+}
+
+/// Brings the copy of `url` cached at `path` up to date. The `ETag` and
+/// `Last-Modified` of the cached copy, saved in `{path}.headers`, are sent
+/// along, so that an unchanged list isn't downloaded again. When the download
+/// fails the cached copy is kept, if there is one.
+pub fn refresh(url: &str, path: &str) -> Result<(), String> {
+    let headers_path = format!("{}.headers", path);
+    let mut request = ureq::get(url);
+    if Path::new(path).exists() {
+        let saved = fs::read_to_string(&headers_path).unwrap_or_default();
+        for (name, value) in saved.lines().filter_map(|line| line.split_once(": ")) {
+            if let Some((_, condition)) = VALIDATORS.iter().find(|(header, _)| *header == name) {
+                request = request.set(condition, value);
+            }
+        }
+    }
+
+    match request.call().map_err(io::Error::other).and_then(|response| save(response, path, &headers_path)) {
+        Ok(true) => eprintln!("corpus {}: downloaded to {}", url, path),
+        Ok(false) => eprintln!("corpus {}: not modified", url),
+        Err(e) if Path::new(path).exists() => eprintln!("corpus {}: download failed, using the cached copy: {}", url, e),
+        Err(e) => return Err(format!("Failed to download corpus {}: {}", url, e)),
+    }
+    Ok(())
+}
+
+/// Writes a new download over the cached copy, returning whether there was
+/// one.
+fn save(response: ureq::Response, path: &str, headers_path: &str) -> io::Result<bool> {
+    if response.status() == 304 {
+        return Ok(false);
+    }
+    let headers = VALIDATORS.iter()
+        .filter_map(|(header, _)| response.header(header).map(|value| format!("{}: {}\n", header, value)))
+        .collect::<String>();
+    let partial = format!("{}.part", path);
+    io::copy(&mut response.into_reader(), &mut fs::File::create(&partial)?)?;
+    fs::rename(&partial, path)?;
+    fs::write(headers_path, headers)?;
+    Ok(true)
+}
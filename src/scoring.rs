@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use itertools::Itertools;
+
+use crate::feedback::{self, Tile};
+
+/// How good a guess is according to each strategy.
+#[derive(Debug, Copy, Clone)]
+pub struct GuessScore {
+    pub entropy: f64,
+    pub worst_case: usize,
+    pub expected_remaining: f64,
+}
+
+impl GuessScore {
+    pub fn new(buckets: &HashMap<Vec<Tile>, usize>) -> Self {
+        GuessScore {
+            entropy: entropy(buckets),
+            worst_case: worst_case(buckets),
+            expected_remaining: expected_remaining(buckets),
+        }
+    }
+}
+
+pub fn get_frequency(word: &str) -> Vec<(char, usize)> {
+    word.chars().sorted().group_by(|c| *c).into_iter().map(|(c, g)| (c, g.count())).collect()
+}
+
+/// Number of words containing each letter, most common letters first.
+pub fn letter_counts<T: AsRef<str>>(words: &[T]) -> Vec<(char, usize)> {
+    let mut freq = words.iter().map(|w| get_frequency(w.as_ref())).fold(Vec::new(), |mut acc, f| {
+        for (c, _) in f {
+            match acc.iter().enumerate().find(|(_, (l, _))| l == &c) {
+                Some((idx, _)) => acc[idx].1 += 1,
+                None => acc.push((c, 1_usize)),
+            }
+        }
+        acc
+    });
+    freq.sort_by_key(|(_, f)| *f);
+    freq.into_iter().rev().collect()
+}
+
+/// How evenly the letters of `word` split `total` candidates, given how many
+/// candidates contain each letter.
+pub fn split_score(counts: &[(char, usize)], total: usize, word: &str) -> usize {
+    get_frequency(word).iter()
+        .filter_map(|(c, _)| counts.iter().find(|(l, _)| l == c))
+        .map(|(_, k)| (*k).min(total - k))
+        .sum()
+}
+
+pub fn to_chars<T: AsRef<str>>(words: &[T]) -> Vec<Vec<char>> {
+    words.iter().map(|w| w.as_ref().chars().collect()).collect()
+}
+
+/// Like `partition`, but keeps the candidates of every bucket.
+pub fn split(guess: &[char], candidates: &[Vec<char>]) -> HashMap<Vec<Tile>, Vec<Vec<char>>> {
+    let mut buckets: HashMap<_, Vec<_>> = HashMap::new();
+    for answer in candidates {
+        buckets.entry(feedback::grade_chars(answer, guess)).or_default().push(answer.clone());
+    }
+    buckets
+}
+
+/// The feedback an adversary would give to `guess`: the one leaving the most
+/// candidates, preferring fewer green and then fewer yellow tiles on ties, as
+/// Absurdle does. Returns the feedback with the candidates it leaves.
+pub fn adversarial(guess: &[char], candidates: &[Vec<char>]) -> Option<(Vec<Tile>, Vec<Vec<char>>)> {
+    let count = |f: &[Tile], tile| f.iter().filter(|s| **s == tile).count();
+    split(guess, candidates).into_iter().max_by(|(fa, a), (fb, b)| {
+        a.len().cmp(&b.len())
+            .then(count(fb, Tile::Correct).cmp(&count(fa, Tile::Correct)))
+            .then(count(fb, Tile::Misplaced).cmp(&count(fa, Tile::Misplaced)))
+            .then(fb.cmp(fa))
+    })
+}
+
+/// Groups the candidates by the feedback `guess` would get if each of them
+/// were the answer, counting the candidates in every bucket.
+pub fn partition(guess: &[char], candidates: &[Vec<char>]) -> HashMap<Vec<Tile>, usize> {
+    let mut buckets = HashMap::new();
+    for answer in candidates {
+        *buckets.entry(feedback::grade_chars(answer, guess)).or_default() += 1;
+    }
+    buckets
+}
+
+/// Like `partition`, for a sequence of guesses played one after the other.
+pub fn partition_many(guesses: &[Vec<char>], candidates: &[Vec<char>]) -> HashMap<Vec<Tile>, usize> {
+    let mut buckets = HashMap::new();
+    for answer in candidates {
+        let key = guesses.iter().flat_map(|g| feedback::grade_chars(answer, g)).collect();
+        *buckets.entry(key).or_default() += 1;
+    }
+    buckets
+}
+
+/// Expected information, in bits, revealed by the feedback of a guess.
+pub fn entropy(buckets: &HashMap<Vec<Tile>, usize>) -> f64 {
+    let total = buckets.values().sum::<usize>() as f64;
+    buckets.values()
+        .map(|&n| n as f64 / total)
+        .map(|p| p * (1.0 / p).log2())
+        .sum()
+}
+
+/// Size of the largest bucket: how many candidates can be left after the guess.
+pub fn worst_case(buckets: &HashMap<Vec<Tile>, usize>) -> usize {
+    buckets.values().copied().max().unwrap_or(0)
+}
+
+/// Average number of candidates left after the guess, when every candidate is
+/// equally likely to be the answer.
+pub fn expected_remaining(buckets: &HashMap<Vec<Tile>, usize>) -> f64 {
+    let total = buckets.values().sum::<usize>() as f64;
+    buckets.values().map(|&n| (n * n) as f64).sum::<f64>() / total.max(1.0)
+}
+
+/// Green and yellow tiles in a feedback.
+pub fn hits(feedback: &[Tile]) -> usize {
+    feedback.iter().filter(|f| **f != Tile::Absent).count()
+}
@@ -0,0 +1,74 @@
+use crate::feedback::Tile;
+
+fn tile_digit(c: char) -> Option<char> {
+    match c {
+        '🟩' | '🟧' => Some('2'),
+        '🟨' | '🟦' => Some('1'),
+        '⬛' | '⬜' => Some('0'),
+        _ => None,
+    }
+}
+
+/// Extracts the feedback rows from pasted share text as digit strings
+/// (`21000`), skipping header lines such as `Wordle 1,234 4/6`. Both the
+/// regular and the high-contrast palettes are understood.
+pub fn parse_rows(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.chars().filter(|c| !c.is_whitespace() && *c != '\u{fe0f}').collect::<Vec<_>>())
+        .filter(|tiles| !tiles.is_empty())
+        .filter_map(|tiles| tiles.into_iter().map(tile_digit).collect::<Option<String>>())
+        .collect()
+}
+
+fn tile_emoji(tile: Tile, high_contrast: bool) -> char {
+    match (tile, high_contrast) {
+        (Tile::Correct, false) => '🟩',
+        (Tile::Correct, true) => '🟧',
+        (Tile::Misplaced, false) => '🟨',
+        (Tile::Misplaced, true) => '🟦',
+        (Tile::Absent, _) => '⬛',
+    }
+}
+
+/// Share text in Wordle's format: a `3/6` score line (`X/6` when unsolved),
+/// a blank line and one row of squares per guess, which `parse_rows` reads
+/// back.
+pub fn render(rows: &[Vec<Tile>], max_guesses: usize, high_contrast: bool) -> String {
+    let solved = rows.last().is_some_and(|row| crate::feedback::solved(row));
+    let score = match solved {
+        true => rows.len().to_string(),
+        false => "X".to_string(),
+    };
+    let grid = rows.iter()
+        .map(|row| row.iter().map(|t| tile_emoji(*t, high_contrast)).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("{}/{}\n\n{}", score, max_guesses, grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_formats() {
+        let cases = [
+            ("Wordle 1,234 3/6\n\n⬛🟨⬛⬛⬛\n⬛⬛🟩🟨⬛\n🟩🟩🟩🟩🟩", vec!["01000", "00210", "22222"]),
+            ("Wordle 1,234 2/6*\n\n🟧🟦⬜⬜⬜\n🟧🟧🟧🟧🟧", vec!["21000", "22222"]),
+            ("⬛️🟨️⬛️⬛️⬛️", vec!["01000"]),
+            ("🟩 🟩 🟩 🟩 🟩", vec!["22222"]),
+            ("no squares here", vec![]),
+        ];
+        for (text, rows) in cases {
+            assert_eq!(parse_rows(text), rows, "{}", text);
+        }
+    }
+
+    #[test]
+    fn rendered_text_reads_back() {
+        let rows = vec![vec![Tile::Absent, Tile::Misplaced, Tile::Absent, Tile::Absent, Tile::Absent], vec![Tile::Correct; 5]];
+        for high_contrast in [false, true] {
+            assert_eq!(parse_rows(&render(&rows, 6, high_contrast)), ["01000", "22222"]);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::{collections::{hash_map::DefaultHasher, HashMap}, fs, hash::{Hash, Hasher}, io, path::Path, time::UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::CaseFolding;
+use crate::corpora::CorpusFiles;
+
+/// Bumped whenever `Snapshot` or the way words are cleaned changes, so that
+/// older snapshots are rebuilt.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// A corpus as loaded from its word lists, grouped by length, with its
+/// frequency table; reading it back skips cleaning and normalizing every word.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The files and settings it was made from, sizes and modification times
+    /// included: any change makes the snapshot stale.
+    key: String,
+    pub corpus: Vec<(usize, Vec<String>)>,
+    pub answers: Vec<(usize, Vec<String>)>,
+    pub frequencies: HashMap<String, u64>,
+}
+
+/// Where the snapshot of `files` is kept in `dir` (`CORPUS_SNAPSHOT_DIR`).
+pub fn path(dir: &str, files: &CorpusFiles) -> String {
+    let mut hasher = DefaultHasher::new();
+    files.corpus.hash(&mut hasher);
+    Path::new(dir).join(format!("{:016x}.bin", hasher.finish())).to_string_lossy().into_owned()
+}
+
+/// Describes the files a snapshot depends on as they are now.
+pub fn key(files: &CorpusFiles, case: CaseFolding) -> String {
+    let paths = [Some(&files.corpus), files.answers.as_ref(), files.alphabet.as_ref(), files.frequencies.as_ref()];
+    let stamps = paths.iter().map(|path| {
+        let stamp = path.and_then(|p| fs::metadata(p).ok()).map(|m| {
+            let modified = m.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).unwrap_or_default();
+            format!("{}:{}", m.len(), modified.as_nanos())
+        });
+        format!("{}@{}", path.map_or("", |p| p.as_str()), stamp.unwrap_or_default())
+    });
+    format!("v{} case={:?} fold={} {}", SNAPSHOT_VERSION, case, files.fold_diacritics, stamps.collect::<Vec<_>>().join(" "))
+}
+
+/// The snapshot at `path`, if it was made under `key`.
+pub fn read(path: &str, key: &str) -> Option<Snapshot> {
+    let snapshot: Snapshot = bincode::deserialize(&fs::read(path).ok()?).ok()?;
+    (snapshot.key == key).then_some(snapshot)
+}
+
+pub fn write(path: &str, key: String, corpus: &[(usize, Vec<String>)], answers: &[(usize, Vec<String>)], frequencies: &HashMap<String, u64>) -> io::Result<()> {
+    let snapshot = Snapshot { key, corpus: corpus.to_vec(), answers: answers.to_vec(), frequencies: frequencies.clone() };
+    let bytes = bincode::serialize(&snapshot).map_err(io::Error::other)?;
+    let partial = format!("{}.part", path);
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, path)
+}
@@ -0,0 +1,226 @@
+use std::{cmp::Ordering, collections::HashSet, sync::LazyLock};
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::scoring::{self, GuessScore};
+
+/// Ranks guesses against the words that can still be the answer.
+pub trait Strategy {
+    /// Every guess in `guesses` as its index and a score where higher is
+    /// better, best first.
+    fn rank(&self, candidates: &[Vec<char>], guesses: &[Vec<char>]) -> Vec<(usize, f64)>;
+}
+
+/// Letters that split the candidates most evenly, as `/api/solve` does.
+pub struct LetterFrequency;
+/// Most information on average.
+pub struct Entropy;
+/// Fewest candidates left in the worst case.
+pub struct Minimax;
+/// Fewest candidates left on average.
+pub struct ExpectedRemaining;
+
+/// Letter frequency while there are more than `heuristic_above` candidates,
+/// where scoring every feedback is too slow to be worth it, minimax once
+/// there are `minimax_up_to` or fewer, and entropy in between. Read from
+/// `ADAPTIVE_HEURISTIC_ABOVE` and `ADAPTIVE_MINIMAX_UP_TO`.
+pub struct Adaptive {
+    heuristic_above: usize,
+    minimax_up_to: usize,
+}
+
+const DEFAULT_ADAPTIVE_HEURISTIC_ABOVE: usize = 2_000;
+const DEFAULT_ADAPTIVE_MINIMAX_UP_TO: usize = 20;
+
+static ADAPTIVE: LazyLock<Adaptive> = LazyLock::new(Adaptive::from_env);
+
+impl Adaptive {
+    fn from_env() -> Adaptive {
+        Adaptive {
+            heuristic_above: crate::env_parse("ADAPTIVE_HEURISTIC_ABOVE").unwrap_or(DEFAULT_ADAPTIVE_HEURISTIC_ABOVE),
+            minimax_up_to: crate::env_parse("ADAPTIVE_MINIMAX_UP_TO").unwrap_or(DEFAULT_ADAPTIVE_MINIMAX_UP_TO),
+        }
+    }
+
+    /// Reads the thresholds now, so that invalid values fail at startup.
+    pub fn init() {
+        LazyLock::force(&ADAPTIVE);
+    }
+
+    /// The strategy played against `candidates` candidates.
+    fn pick(&self, candidates: usize) -> StrategyKind {
+        match candidates {
+            n if n > self.heuristic_above => StrategyKind::LetterFrequency,
+            n if n <= self.minimax_up_to => StrategyKind::Minimax,
+            _ => StrategyKind::Entropy,
+        }
+    }
+}
+
+impl Strategy for LetterFrequency {
+    fn rank(&self, candidates: &[Vec<char>], guesses: &[Vec<char>]) -> Vec<(usize, f64)> {
+        let words = candidates.iter().map(|c| c.iter().collect::<String>()).collect::<Vec<_>>();
+        let counts = scoring::letter_counts(&words);
+        guesses.iter().enumerate()
+            .map(|(i, g)| (i, scoring::split_score(&counts, words.len(), &g.iter().collect::<String>()) as f64))
+            .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+            .collect()
+    }
+}
+
+/// Ranks by the feedback buckets of each guess, ordered as `kind` compares
+/// them and scored with `value`.
+fn rank_buckets(kind: StrategyKind, candidates: &[Vec<char>], guesses: &[Vec<char>], value: impl Fn(&GuessScore) -> f64) -> Vec<(usize, f64)> {
+    guesses.iter().enumerate()
+        .map(|(i, g)| (i, GuessScore::new(&scoring::partition(g, candidates))))
+        .sorted_by(|(_, a), (_, b)| kind.compare(a, b))
+        .map(|(i, s)| (i, value(&s)))
+        .collect()
+}
+
+impl Strategy for Entropy {
+    fn rank(&self, candidates: &[Vec<char>], guesses: &[Vec<char>]) -> Vec<(usize, f64)> {
+        rank_buckets(StrategyKind::Entropy, candidates, guesses, |s| s.entropy)
+    }
+}
+
+impl Strategy for Minimax {
+    fn rank(&self, candidates: &[Vec<char>], guesses: &[Vec<char>]) -> Vec<(usize, f64)> {
+        rank_buckets(StrategyKind::Minimax, candidates, guesses, |s| -(s.worst_case as f64))
+    }
+}
+
+impl Strategy for ExpectedRemaining {
+    fn rank(&self, candidates: &[Vec<char>], guesses: &[Vec<char>]) -> Vec<(usize, f64)> {
+        rank_buckets(StrategyKind::ExpectedRemaining, candidates, guesses, |s| -s.expected_remaining)
+    }
+}
+
+impl Strategy for Adaptive {
+    fn rank(&self, candidates: &[Vec<char>], guesses: &[Vec<char>]) -> Vec<(usize, f64)> {
+        self.pick(candidates.len()).strategy().rank(candidates, guesses)
+    }
+}
+
+/// Which `Strategy` ranks guesses, read from the `strategy` query parameter.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    LetterFrequency,
+    #[default]
+    Entropy,
+    Minimax,
+    ExpectedRemaining,
+    /// Fewest guesses on average, searched exhaustively; only `/api/suggest`
+    /// runs that search, elsewhere it ranks like `ExpectedRemaining`.
+    Optimal,
+    /// Switches strategy with the number of candidates, see `Adaptive`.
+    Adaptive,
+}
+
+fn default_depth() -> usize {
+    1
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+pub struct StrategyQuery {
+    #[serde(default)]
+    pub strategy: StrategyKind,
+    /// 2 also plays the best follow-up guess in every feedback bucket.
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+}
+
+impl StrategyKind {
+    pub fn strategy(&self) -> &'static dyn Strategy {
+        match self {
+            StrategyKind::LetterFrequency => &LetterFrequency,
+            StrategyKind::Entropy => &Entropy,
+            StrategyKind::Minimax => &Minimax,
+            StrategyKind::ExpectedRemaining | StrategyKind::Optimal => &ExpectedRemaining,
+            StrategyKind::Adaptive => &*ADAPTIVE,
+        }
+    }
+
+    /// The strategy actually played against `candidates` candidates, which is
+    /// only another one for `Adaptive`.
+    pub fn resolve(self, candidates: usize) -> StrategyKind {
+        match self {
+            StrategyKind::Adaptive => ADAPTIVE.pick(candidates),
+            kind => kind,
+        }
+    }
+
+    /// Orders better bucket scores first; ties fall back on entropy, then on
+    /// the worst case. Letter frequency has no bucket score of its own and
+    /// compares like entropy, as does the adaptive strategy.
+    pub fn compare(&self, a: &GuessScore, b: &GuessScore) -> Ordering {
+        let by_entropy = b.entropy.total_cmp(&a.entropy);
+        let by_worst_case = a.worst_case.cmp(&b.worst_case);
+        match self {
+            StrategyKind::LetterFrequency | StrategyKind::Entropy | StrategyKind::Adaptive => by_entropy.then(by_worst_case),
+            StrategyKind::Minimax => by_worst_case.then(by_entropy),
+            StrategyKind::ExpectedRemaining | StrategyKind::Optimal => a.expected_remaining.total_cmp(&b.expected_remaining)
+                .then(by_entropy)
+                .then(by_worst_case),
+        }
+    }
+}
+
+/// Scores `guess` two plies deep: after its feedback, the best of `guesses`
+/// according to `kind` is played in every bucket, and the scores describe
+/// what is left after that second guess. Entropy adds up the information of
+/// both guesses.
+pub fn lookahead(kind: StrategyKind, guess: &[char], candidates: &[Vec<char>], guesses: &[Vec<char>]) -> GuessScore {
+    let total = candidates.len().max(1) as f64;
+    let mut result = GuessScore { entropy: scoring::entropy(&scoring::partition(guess, candidates)), worst_case: 0, expected_remaining: 0.0 };
+    for bucket in scoring::split(guess, candidates).into_values() {
+        let follow_up = match bucket.len() {
+            1 => &bucket[0],
+            _ => kind.strategy().rank(&bucket, guesses).first().map_or(guess, |(i, _)| &guesses[*i]),
+        };
+        let follow_up = GuessScore::new(&scoring::partition(follow_up, &bucket));
+        let p = bucket.len() as f64 / total;
+        result.entropy += p * follow_up.entropy;
+        result.worst_case = result.worst_case.max(follow_up.worst_case);
+        result.expected_remaining += p * follow_up.expected_remaining;
+    }
+    result
+}
+
+/// The guess `kind` would play next. Ties go to guesses that may still be
+/// the answer, and with two candidates or fewer one of them is always played.
+pub fn best_guess<'a>(kind: StrategyKind, guesses: &'a [Vec<char>], candidates: &'a [Vec<char>]) -> Option<&'a [char]> {
+    if candidates.len() <= 2 {
+        return candidates.first().map(Vec::as_slice);
+    }
+    let ranked = kind.strategy().rank(candidates, guesses);
+    let (_, top) = *ranked.first()?;
+    let tied = ranked.iter().take_while(|(_, s)| *s == top).map(|(i, _)| &guesses[*i]).collect::<Vec<_>>();
+    let candidate_set = candidates.iter().collect::<HashSet<_>>();
+    tied.iter().find(|g| candidate_set.contains(*g)).or(tied.first()).map(|g| g.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::to_chars;
+
+    #[test]
+    fn adaptive_switches_strategy_by_candidate_count() {
+        let adaptive = Adaptive { heuristic_above: 100, minimax_up_to: 5 };
+        assert_eq!(adaptive.pick(101), StrategyKind::LetterFrequency);
+        assert_eq!(adaptive.pick(100), StrategyKind::Entropy);
+        assert_eq!(adaptive.pick(6), StrategyKind::Entropy);
+        assert_eq!(adaptive.pick(5), StrategyKind::Minimax);
+    }
+
+    #[test]
+    fn adaptive_plays_minimax_on_few_candidates() {
+        let adaptive = Adaptive { heuristic_above: 100, minimax_up_to: 5 };
+        let candidates = to_chars(&["bat", "cat", "hat", "mat"]);
+        // TBC leaves HAT and MAT together, BCH tells all four apart.
+        let ranked = adaptive.rank(&candidates, &to_chars(&["tbc", "bch"]));
+        assert_eq!(ranked.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![1, 0]);
+    }
+}
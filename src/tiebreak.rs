@@ -0,0 +1,47 @@
+use std::cmp::Ordering;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreakMode {
+    /// Keep corpus order.
+    #[default]
+    Corpus,
+    Alphabetical,
+    /// Shuffle deterministically from `seed`.
+    Random,
+    /// Words that can still be the answer go first.
+    AnswerFirst,
+    /// Most used words first, as given by `FREQUENCY_FILE`.
+    Frequency,
+}
+
+/// How to order suggestions that share the same score, read from the
+/// `tiebreak` and `seed` query parameters.
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+pub struct TieBreak {
+    #[serde(default)]
+    tiebreak: TieBreakMode,
+    #[serde(default)]
+    seed: u64,
+}
+
+/// FNV-1a over the seed and the word, so that a given seed shuffles a list the
+/// same way on every run and every build.
+fn seeded_hash(seed: u64, word: &str) -> u64 {
+    seed.to_le_bytes().iter().chain(word.as_bytes()).fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl TieBreak {
+    pub fn compare(&self, a: &str, b: &str, is_answer: impl Fn(&str) -> bool, frequency: impl Fn(&str) -> u64) -> Ordering {
+        match self.tiebreak {
+            TieBreakMode::Corpus => Ordering::Equal,
+            TieBreakMode::Alphabetical => a.cmp(b),
+            TieBreakMode::Random => seeded_hash(self.seed, a).cmp(&seeded_hash(self.seed, b)),
+            TieBreakMode::AnswerFirst => is_answer(b).cmp(&is_answer(a)),
+            TieBreakMode::Frequency => frequency(b).cmp(&frequency(a)),
+        }
+    }
+}
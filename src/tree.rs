@@ -0,0 +1,129 @@
+use std::{collections::HashMap, sync::{Mutex, PoisonError}};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::encoding::Protobuf;
+use crate::feedback::{self, Tile};
+use crate::strategy::{self, StrategyKind};
+use crate::wordset::WordSet;
+
+/// A guess and what to play next after each feedback it can get. The winning
+/// feedback has no branch.
+#[derive(Debug, Clone, Serialize)]
+pub struct Node {
+    guess: String,
+    candidates: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    branches: Vec<Branch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Branch {
+    feedback: String,
+    next: Node,
+}
+
+impl Protobuf for Node {}
+
+impl Node {
+    fn size(&self) -> usize {
+        1 + self.branches.iter().map(|b| b.next.size()).sum::<usize>()
+    }
+}
+
+/// Subtrees already built for a set of candidates, by strategy. The subtree
+/// only depends on those, so it can be shared by every tree reaching the
+/// same set. Candidate sets record their word length, so trees of different
+/// lengths never share a subtree.
+pub type Subtrees = HashMap<(StrategyKind, WordSet), Node>;
+
+struct Builder<'a> {
+    strategy: StrategyKind,
+    /// Guesses played at the first levels instead of the strategy's.
+    opening: &'a [Vec<char>],
+    guesses: &'a [Vec<char>],
+    answers: &'a [Vec<char>],
+    /// Looked up without holding the lock during the build.
+    shared: &'a Mutex<Subtrees>,
+    /// Subtrees built by this tree, added to `shared` once it is done.
+    built: Subtrees,
+    nodes: usize,
+    max_nodes: usize,
+}
+
+impl Builder<'_> {
+    fn node(&mut self, guess: &[char], ids: &WordSet, depth: usize) -> Option<Node> {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return None;
+        }
+
+        let mut buckets: HashMap<Vec<Tile>, WordSet> = HashMap::new();
+        for id in ids.iter() {
+            buckets.entry(feedback::grade_chars(&self.answers[id], guess))
+                .or_insert_with(|| WordSet::from_ids(ids.length(), self.answers.len(), []))
+                .insert(id);
+        }
+        let mut branches = Vec::new();
+        for (feedback, bucket) in buckets.into_iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+            if feedback::solved(&feedback) {
+                continue;
+            }
+            if let Some(pinned) = self.opening.get(depth + 1) {
+                let next = self.node(pinned, &bucket, depth + 1)?;
+                branches.push(Branch { feedback: feedback::to_digits(&feedback), next });
+                continue;
+            }
+            let key = (self.strategy, bucket.clone());
+            let cached = self.built.get(&key).cloned()
+                .or_else(|| self.shared.lock().unwrap_or_else(PoisonError::into_inner).get(&key).cloned());
+            let next = match cached {
+                Some(next) => {
+                    self.nodes += next.size() - 1;
+                    next
+                },
+                None => {
+                    let candidates = bucket.iter().map(|id| self.answers[id].clone()).collect::<Vec<_>>();
+                    let next_guess = strategy::best_guess(self.strategy, self.guesses, &candidates)?.to_vec();
+                    let next = self.node(&next_guess, &bucket, depth + 1)?;
+                    self.built.insert(key, next.clone());
+                    next
+                },
+            };
+            branches.push(Branch { feedback: feedback::to_digits(&feedback), next });
+        }
+
+        Some(Node { guess: guess.iter().collect(), candidates: ids.len(), branches })
+    }
+}
+
+/// The full tree of guesses `strategy` plays against every answer after the
+/// `opening` guesses, which are played in order whatever the feedback, or
+/// `None` if it would have more than `max_nodes` nodes. `opening` holds at
+/// least the opener. The subtrees built on the way are added to `subtrees`,
+/// even when the tree is too large, but the lock is only held to look them up
+/// and to add them.
+pub fn build(strategy: StrategyKind, opening: &[Vec<char>], guesses: &[Vec<char>], answers: &[Vec<char>], subtrees: &Mutex<Subtrees>, max_nodes: usize) -> Option<Node> {
+    let ids = WordSet::from_fn(opening[0].len(), answers.len(), |_| true);
+    let mut builder = Builder { strategy, opening, guesses, answers, shared: subtrees, built: HashMap::new(), nodes: 0, max_nodes };
+    let tree = builder.node(&opening[0], &ids, 0);
+    subtrees.lock().unwrap_or_else(PoisonError::into_inner).extend(builder.built);
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::to_chars;
+
+    #[test]
+    fn lengths_sharing_a_cache_keep_their_own_subtrees() {
+        let subtrees = Mutex::new(Subtrees::new());
+        let three = to_chars(&["bat", "cat", "hat"]);
+        let four = to_chars(&["bolt", "colt", "holt"]);
+        // Both openers leave the candidates with IDs 1 and 2.
+        build(StrategyKind::Entropy, &three[..1], &three, &three, &subtrees, 100).unwrap();
+        let tree = build(StrategyKind::Entropy, &four[..1], &four, &four, &subtrees, 100).unwrap();
+        assert_eq!(tree.branches[0].next.guess, "colt");
+    }
+}
@@ -0,0 +1,78 @@
+/// A set of word IDs, where a word's ID is its index in the corpus list of
+/// its length. Editing a corpus builds a new state with new lists, so sets
+/// built from the same state can be intersected, compared and hashed
+/// directly, but must not outlive it. The set records that length, so that
+/// sets of different lengths never compare equal, even with the same IDs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WordSet {
+    length: usize,
+    bits: Vec<u64>,
+}
+
+impl WordSet {
+    /// The IDs below `capacity` of the words of `length` letters for which
+    /// `contains` returns true.
+    pub fn from_fn(length: usize, capacity: usize, mut contains: impl FnMut(usize) -> bool) -> Self {
+        let mut bits = vec![0; capacity.div_ceil(64)];
+        for id in (0..capacity).filter(|&id| contains(id)) {
+            bits[id / 64] |= 1 << (id % 64);
+        }
+        WordSet { length, bits }
+    }
+
+    pub fn from_ids(length: usize, capacity: usize, ids: impl IntoIterator<Item = usize>) -> Self {
+        let mut bits = vec![0; capacity.div_ceil(64)];
+        for id in ids {
+            bits[id / 64] |= 1 << (id % 64);
+        }
+        WordSet { length, bits }
+    }
+
+    /// The length of the words whose IDs the set holds.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn insert(&mut self, id: usize) {
+        self.bits[id / 64] |= 1 << (id % 64);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|b| *b == 0)
+    }
+
+    /// Keeps only the IDs for which `keep` returns true, without visiting the
+    /// IDs that are already out of the set.
+    pub fn retain(&mut self, mut keep: impl FnMut(usize) -> bool) {
+        for id in self.iter().collect::<Vec<_>>() {
+            if !keep(id) {
+                self.bits[id / 64] &= !(1 << (id % 64));
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(block, bits)| {
+            (0..64).filter(move |bit| bits & (1 << bit) != 0).map(move |bit| block * 64 + bit)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn sets_of_different_lengths_differ() {
+        let three = WordSet::from_ids(3, 10, [1, 2]);
+        let four = WordSet::from_ids(4, 10, [1, 2]);
+        assert_ne!(three, four);
+        assert_eq!(HashSet::from([three.clone(), four, WordSet::from_fn(3, 10, |id| id == 1 || id == 2)]).len(), 2);
+        assert_eq!(three.iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+}